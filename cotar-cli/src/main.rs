@@ -1,10 +1,14 @@
-use clap::{Parser, Subcommand};
-use cotar::CotarIndex;
+use clap::error::ErrorKind;
+use clap::{ArgAction, Args, CommandFactory, Parser, Subcommand, ValueEnum};
+use cotar::{CotarIndex, CotarResult, HashAlgorithm};
 use std::fs::File;
 use std::io::Write;
 use std::path::Path;
 use std::process;
 use std::time::Instant;
+use tracing::{error, info, info_span};
+use tracing_subscriber::filter::LevelFilter;
+use tracing_subscriber::EnvFilter;
 
 mod mbtiles;
 mod validate;
@@ -14,6 +18,31 @@ mod validate;
 struct Cli {
     #[clap(subcommand)]
     command: Commands,
+
+    /// Log more, "-v" for debug and "-vv" for trace
+    #[clap(short, long, action = ArgAction::Count, global = true)]
+    verbose: u8,
+
+    /// Log less, "-q" for warnings and "-qq" for errors only
+    #[clap(short, long, action = ArgAction::Count, global = true, conflicts_with = "verbose")]
+    quiet: u8,
+
+    /// Format to write logs in
+    ///
+    /// Default: text
+    #[clap(long, global = true)]
+    log_format: Option<LogFormat>,
+}
+
+#[derive(Clone, Copy, Default, ValueEnum)]
+enum LogFormat {
+    /// Human readable single line logs
+    #[default]
+    Text,
+    /// Human readable multi line logs
+    Pretty,
+    /// Newline delimited JSON
+    Json,
 }
 
 #[derive(Subcommand)]
@@ -37,6 +66,9 @@ enum Commands {
         /// Default: 100
         #[clap(short = 'm', long)]
         max_search: Option<usize>,
+
+        #[clap(flatten)]
+        index: IndexArgs,
     },
 
     /// Create a tar index for a tar
@@ -58,6 +90,9 @@ enum Commands {
         /// Default: 100
         #[clap(short = 'm', long)]
         max_search: Option<usize>,
+
+        #[clap(flatten)]
+        index: IndexArgs,
     },
 
     /// Validate tar index
@@ -91,85 +126,202 @@ enum Commands {
     },
 }
 
+/// Options shared by every command that writes a tar index
+#[derive(Args)]
+struct IndexArgs {
+    /// Index version to write, v3 supports tars larger than 2TiB and files larger than 4GiB
+    ///
+    /// Default: 2, or 3 if the hash algorithm is not fnv1a
+    #[clap(long, value_parser = clap::value_parser!(u8).range(2..=3))]
+    index_version: Option<u8>,
+
+    /// Algorithm to hash file paths with: fnv1a, xxh3 or siphash
+    ///
+    /// Default: fnv1a
+    #[clap(long)]
+    hash: Option<HashAlgorithm>,
+
+    /// Write a table of every file path after the index in the .index file, to list files and avoid hash collisions
+    ///
+    /// Default: false
+    #[clap(long)]
+    path_table: Option<bool>,
+}
+
+impl IndexArgs {
+    /// Fails if the hash algorithm cannot be stored in the requested index version
+    fn options(&self) -> Result<IndexOptions, clap::Error> {
+        let hash = self.hash.unwrap_or_default();
+        if self.index_version == Some(2) && hash != HashAlgorithm::Fnv1a {
+            return Err(Cli::command().bin_name(env!("CARGO_BIN_NAME")).error(
+                ErrorKind::ArgumentConflict,
+                format!(
+                    "--hash {hash} needs --index-version 3, v2 indexes only store fnv1a hashes"
+                ),
+            ));
+        }
+        Ok(IndexOptions {
+            version: self.index_version,
+            hash,
+            path_table: self.path_table.unwrap_or(false),
+        })
+    }
+}
+
 const MAX_SEARCH: usize = 100;
 
-fn create(file_name: &str, force: bool, max_search: usize) {
-    file_index_create(file_name, force, max_search);
+/// Options for packing a tar index
+#[derive(Default)]
+struct IndexOptions {
+    /// Index version, defaults to the smallest version that can store the hash algorithm
+    version: Option<u8>,
+    hash: HashAlgorithm,
+    /// Write a path table after the index
+    path_table: bool,
+}
+
+/// Write logs to stderr, `RUST_LOG` overrides the level from `-v` and `-q`
+fn init_logging(verbose: u8, quiet: u8, format: LogFormat) {
+    let level = match (verbose, quiet) {
+        (0, 0) => LevelFilter::INFO,
+        (1, _) => LevelFilter::DEBUG,
+        (_, 0) => LevelFilter::TRACE,
+        (_, 1) => LevelFilter::WARN,
+        _ => LevelFilter::ERROR,
+    };
+    let filter = EnvFilter::builder()
+        .with_default_directive(level.into())
+        .from_env_lossy();
+    let builder = tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(std::io::stderr);
+    match format {
+        LogFormat::Text => builder.init(),
+        LogFormat::Pretty => builder.pretty().init(),
+        LogFormat::Json => builder.json().init(),
+    }
 }
 
-fn file_index_create(file_name: &str, force: bool, max_search: usize) {
+fn create(
+    file_name: &str,
+    force: bool,
+    max_search: usize,
+    options: IndexOptions,
+) -> CotarResult<()> {
+    file_index_create(file_name, force, max_search, options)
+}
+
+fn file_index_create(
+    file_name: &str,
+    force: bool,
+    max_search: usize,
+    options: IndexOptions,
+) -> CotarResult<()> {
     if !file_name.ends_with(".tar") {
-        println!("❌ {} does not end with .tar", file_name);
+        error!(file_name, "File does not end with .tar");
         process::exit(1);
     }
 
     let index_file_name = format!("{}.index", file_name);
     if !force && Path::new(index_file_name.as_str()).exists() {
-        println!("❌ {} already exists", index_file_name);
+        error!(index_file_name, "Index file already exists");
         process::exit(1);
     }
 
-    println!(
-        "Creating tar index from:{} max_search:{}",
-        file_name, max_search
+    let mut cotar_index = CotarIndex::with_hash_algorithm(options.hash);
+    if let Some(version) = options.version {
+        cotar_index.set_version(version);
+    }
+    cotar_index.set_path_table(options.path_table);
+    info!(
+        file_name,
+        max_search,
+        version = cotar_index.version(),
+        hash = %cotar_index.hash_algorithm(),
+        "Creating tar index"
     );
-    let mut cotar_index = CotarIndex::from_tar(file_name, 100_000).unwrap();
-    println!("Tar read done.. files: {}", cotar_index.entries.len());
+    cotar_index.add_tar(file_name, 100_000)?;
+    info!(files = cotar_index.entries.len(), "Tar read done");
 
     // TODO handle file exists
-    let mut output_file = File::create(index_file_name).expect("Failed to create output file");
+    let mut output_file = File::create(index_file_name)?;
 
-    println!("Packing index..");
+    let _span = info_span!("pack", file_name).entered();
     let mut packing_factor = 1.0;
+    let mut iteration: usize = 0;
     loop {
         packing_factor += 0.0223;
+        iteration += 1;
         let packing_time = Instant::now();
 
-        let output = cotar_index.pack(packing_factor).unwrap();
-        println!(
-            "Index packed! current_factor:{:.2}% search_max:{} search_avg: {:.2} duration:{}ms ",
-            packing_factor * 100.0,
-            output.search_max,
-            output.search_avg,
-            packing_time.elapsed().as_millis()
+        let output = cotar_index.pack(packing_factor)?;
+        info!(
+            iteration,
+            packing_factor = format_args!("{:.2}%", packing_factor * 100.0),
+            search_max = output.search_max,
+            search_avg = format_args!("{:.2}", output.search_avg),
+            duration_ms = packing_time.elapsed().as_millis() as u64,
+            "Index packed"
         );
 
         if output.search_max > max_search {
             continue;
         }
-        output_file.write_all(&output.vec).unwrap();
-        output_file.flush().unwrap();
+        output_file.write_all(&output.vec)?;
+        if let Some(path_table) = &output.path_table {
+            output_file.write_all(path_table)?;
+        }
+        output_file.flush()?;
         break;
     }
 
-    println!("Index packed\n Files: {}", cotar_index.entries.len(),);
+    info!(
+        files = cotar_index.entries.len(),
+        iteration, "Index written"
+    );
+    Ok(())
 }
 
 fn main() {
     let cli = Cli::parse();
+    init_logging(cli.verbose, cli.quiet, cli.log_format.unwrap_or_default());
 
     match &cli.command {
         Commands::Index {
             file_name,
             force,
             max_search,
+            index,
         } => {
-            file_index_create(
+            let options = index.options().unwrap_or_else(|err| err.exit());
+            let result = file_index_create(
                 file_name,
                 force.unwrap_or(false),
                 max_search.unwrap_or(MAX_SEARCH),
+                options,
             );
+            if let Err(err) = result {
+                error!(file_name, %err, "Failed to create index");
+                process::exit(1);
+            }
         }
         Commands::Create {
             file_name,
             force,
             max_search,
+            index,
         } => {
-            create(
+            let options = index.options().unwrap_or_else(|err| err.exit());
+            let result = create(
                 file_name,
                 force.unwrap_or(false),
                 max_search.unwrap_or(MAX_SEARCH),
+                options,
             );
+            if let Err(err) = result {
+                error!(file_name, %err, "Failed to create index");
+                process::exit(1);
+            }
         }
         Commands::FromMbtiles {
             mbtiles_file_name,
@@ -2,15 +2,16 @@ use rusqlite::Connection;
 use std::collections::hash_map::Entry;
 use std::collections::HashMap;
 use std::fs::File;
-use std::io::{Error, ErrorKind, Result as IoResult};
+use std::io::{Error, Result as IoResult};
 use std::time::SystemTime;
 use tar::{Builder, EntryType, Header};
+use tracing::{debug, info, instrument};
 
-use crate::file_index_create;
+use crate::{file_index_create, IndexOptions};
 
 /// Check if the buffer looks like a gziped buffer
 fn is_gzip(buf: &[u8]) -> bool {
-    return buf.len() > 2 && buf[0] == 31 && buf[1] == 139;
+    buf.len() > 2 && buf[0] == 31 && buf[1] == 139
 }
 
 #[derive(Debug)]
@@ -29,7 +30,7 @@ struct SqliteMetadata {
 fn open_mbtiles(file_name: &str) -> IoResult<Connection> {
     let conn = Connection::open(file_name);
     match conn {
-        Err(_e) => Err(Error::new(ErrorKind::Other, "Failed to open ")),
+        Err(_e) => Err(Error::other("Failed to open ")),
         Ok(c) => Ok(c),
     }
 }
@@ -46,7 +47,7 @@ impl TileHashTree {
     }
 
     pub fn insert(&mut self, file_size: usize, hash: u64, path: &str) -> Option<String> {
-        let hm = self.data.entry(file_size).or_insert_with(HashMap::new);
+        let hm = self.data.entry(file_size).or_default();
         let entry = hm.entry(hash);
         match entry {
             Entry::Vacant(e) => {
@@ -66,6 +67,7 @@ impl TileHashTree {
     }
 }
 
+#[instrument(level = "info")]
 pub fn to_tar(
     file_name: &str,
     output_file: &str,
@@ -74,17 +76,17 @@ pub fn to_tar(
     create_index: bool,
 ) -> IoResult<()> {
     if !file_name.ends_with(".mbtiles") {
-        return Err(Error::new(
-            ErrorKind::Other,
-            format!("\"{}\" does not end with .mbtiles", file_name),
-        ));
+        return Err(Error::other(format!(
+            "\"{}\" does not end with .mbtiles",
+            file_name
+        )));
     }
 
     if !output_file.ends_with(".tar") {
-        return Err(Error::new(
-            ErrorKind::Other,
-            format!("\"{}\" does not end with .tar", output_file),
-        ));
+        return Err(Error::other(format!(
+            "\"{}\" does not end with .tar",
+            output_file
+        )));
     }
 
     let conn = open_mbtiles(file_name)?;
@@ -101,14 +103,13 @@ pub fn to_tar(
         progress_count = (tile_count / 20.0).round() as usize
     }
 
-    println!(
-        "MBtiles opened, tiles:{:.0} deduplicate:{} drop_duplicates:{}",
-        tile_count, deduplicate, drop_duplicates
+    info!(
+        tiles = tile_count as u64,
+        deduplicate, drop_duplicates, "MBtiles opened"
     );
 
-    if deduplicate == false && drop_duplicates {
-        return Err(Error::new(
-            ErrorKind::Other,
+    if !deduplicate && drop_duplicates {
+        return Err(Error::other(
             "Cannot use deduplicate:false and drop_duplicates:true",
         ));
     }
@@ -139,7 +140,7 @@ pub fn to_tar(
         let row = meta_row.unwrap();
         if row.name == "format" {
             format = row.value.to_string();
-            println!("Metadata format: {}", format);
+            debug!(format, "Metadata format");
         }
     }
 
@@ -155,7 +156,6 @@ pub fn to_tar(
         .expect("Failed to extract tile");
 
     let mut count: usize = 0;
-    let mut duplicates: usize = 0;
     let start = SystemTime::now();
     let mut current: SystemTime = start;
 
@@ -186,7 +186,6 @@ pub fn to_tar(
                     if drop_duplicates {
                         // Drop them from the output tar
                         // TODO this should be recorded somewhere
-                        duplicates = duplicates + 1;
                     } else {
                         // Duplicate entry store it as a link to the previous entry
                         let mut header = Header::new_gnu();
@@ -204,12 +203,6 @@ pub fn to_tar(
                 .expect("Failed to write file");
         }
 
-        if count == 0 {
-            println!(
-                "{:>10} {:>6.2}% {:>8} {:>32} {}",
-                "count", "", "unique_files", "last_path", "duration"
-            );
-        }
         count += 1;
 
         if count % progress_count == 0 {
@@ -219,13 +212,13 @@ pub fn to_tar(
             };
 
             let now = SystemTime::now();
-            println!(
-                "{:>10} {:>6.1}% {:>8} {:>32} {:.2?}",
+            info!(
                 count,
-                (count as f64 / tile_count) * 100.0,
-                uniques,
-                file_name,
-                now.duration_since(current).unwrap(),
+                progress = format_args!("{:.1}%", (count as f64 / tile_count) * 100.0),
+                unique_files = uniques,
+                last_path = file_name,
+                duration = ?now.duration_since(current).unwrap(),
+                "Converting tiles"
             );
             current = SystemTime::now();
         }
@@ -233,16 +226,16 @@ pub fn to_tar(
 
     tb.finish().expect("Failed to write tar");
 
-    println!(
-        "\n✔️ Tar created: {} from mbtiles entries:{} unique_files:{}\n",
+    info!(
         output_file,
-        count,
-        tht.len(),
+        entries = count,
+        unique_files = tht.len(),
+        "Tar created"
     );
 
     if create_index {
-        file_index_create(output_file, true, 50);
-        println!("✔️ Tar index created: {}.index", output_file);
+        file_index_create(output_file, true, 50, IndexOptions::default()).map_err(Error::other)?;
+        info!(index_file_name = %format!("{}.index", output_file), "Tar index created");
     }
 
     Ok(())
@@ -1,20 +1,21 @@
-use cotar::Cotar;
+use cotar::{Cotar, CotarResult};
 use std::fs::File;
 use std::io::Error;
-use std::io::ErrorKind;
 use std::io::Result as IoResult;
 use tar::{Archive, EntryType};
+use tracing::{error, info, instrument, trace, warn};
 
-pub fn create_cotar(tar_file: &str, tar_index: &Option<String>) -> IoResult<Cotar> {
+pub fn create_cotar(tar_file: &str, tar_index: &Option<String>) -> CotarResult<Cotar> {
     match tar_index {
         Some(tar_index_name) => Cotar::from_tar_index(tar_file, &tar_index_name.to_string()),
         None => Cotar::from_tar(tar_file),
     }
 }
 
+#[instrument(level = "info")]
 pub fn validate(tar_file: &str, tar_index: &Option<String>) -> IoResult<()> {
-    let mut ct = create_cotar(tar_file, tar_index).expect("Failed to open cotar");
-    println!("Cotar v{} Opened, entries: {}", ct.version, ct.entries);
+    let ct = create_cotar(tar_file, tar_index).expect("Failed to open cotar");
+    info!(version = ct.version, entries = ct.entries, "Cotar opened");
 
     let file = File::open(tar_file)?;
     let mut a = Archive::new(file);
@@ -24,28 +25,24 @@ pub fn validate(tar_file: &str, tar_index: &Option<String>) -> IoResult<()> {
     for file in a.entries()? {
         let file = file?;
 
-        let header = file.header();
-        let file_path = header.path()?;
+        let file_path = file.path()?.into_owned();
         let file_name = file_path.to_str().expect("Failed to extract path");
 
+        let header = file.header();
+
         match header.entry_type() {
             EntryType::Regular => {
                 // offset to the file is at end of the header
                 let file_offset = file.raw_header_position() + 512;
-                // let info = ct.info(file_name).expect("Failed to find file");
-                let file_size = header.size()? as u32;
+                let file_size = header.size()?;
 
                 if let Some(info) = ct.info(file_name).expect("Failed to find file") {
                     assert_eq!(info.file_offset, file_offset);
                     assert_eq!(info.file_size, file_size);
-
-                    // println!("Ok {} - {} {} {:?}", file_name, file_offset, file_size, info);
+                    trace!(file_name, file_offset, file_size, "Ok");
                 } else {
-                    println!("Missing Lookup {}", file_name);
-                    return Err(Error::new(
-                        ErrorKind::Other,
-                        format!("Missing file: {}", file_name),
-                    ));
+                    error!(file_name, "Missing lookup");
+                    return Err(Error::other(format!("Missing file: {}", file_name)));
                 }
             }
             EntryType::Link => {
@@ -55,18 +52,18 @@ pub fn validate(tar_file: &str, tar_index: &Option<String>) -> IoResult<()> {
             }
             e => {
                 // Folders/other files??
-                println!("Unknown entry_type: {:?}", e)
+                warn!(entry_type = ?e, "Unknown entry type")
             }
         }
 
         count += 1;
         // If a report is requested dump how far through the file we are.
         if count % 25_000 == 0 {
-            println!("Validated {} / {}", count, ct.entries);
+            info!(count, entries = ct.entries, "Validating");
         }
     }
 
-    println!("✔️ Cotar Validated unique_files:{}", count);
+    info!(unique_files = count, "Cotar validated");
 
     Ok(())
 }
@@ -0,0 +1,50 @@
+use core::fmt;
+
+/// Errors from parsing or searching a cotar index
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum CoreError {
+    /// Index header does not start with "COT"
+    InvalidMagic,
+    /// Index version is not supported by this reader
+    UnsupportedVersion(u8),
+    /// Path hash algorithm is not known or cannot be stored in the index version
+    UnsupportedHashAlgorithm(u8),
+    /// Index is shorter than its header describes
+    TruncatedIndex,
+    /// Index entry points outside of the tar archive
+    OutOfBounds { file_offset: u64, file_size: u64 },
+    /// Read past the end of a byte slice
+    UnexpectedEof { offset: u64, len: u64 },
+}
+
+/// Result type of the cotar core
+pub type CoreResult<T> = core::result::Result<T, CoreError>;
+
+impl fmt::Display for CoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CoreError::InvalidMagic => write!(f, "Invalid magic"),
+            CoreError::UnsupportedVersion(version) => {
+                write!(f, "Unsupported version: {}", version)
+            }
+            CoreError::UnsupportedHashAlgorithm(id) => {
+                write!(f, "Unsupported hash algorithm: {}", id)
+            }
+            CoreError::TruncatedIndex => write!(f, "Truncated index"),
+            CoreError::OutOfBounds {
+                file_offset,
+                file_size,
+            } => write!(
+                f,
+                "Entry out of bounds offset: {} size: {}",
+                file_offset, file_size
+            ),
+            CoreError::UnexpectedEof { offset, len } => {
+                write!(f, "Read out of bounds offset: {} len: {}", offset, len)
+            }
+        }
+    }
+}
+
+impl core::error::Error for CoreError {}
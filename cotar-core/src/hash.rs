@@ -0,0 +1,100 @@
+use alloc::format;
+use alloc::string::String;
+use core::fmt;
+use core::str::FromStr;
+
+use siphasher::sip::SipHasher24;
+use xxhash_rust::xxh3::xxh3_64;
+
+use crate::error::{CoreError, CoreResult};
+use crate::fnv1a::fnv1a_64;
+
+/// Algorithm used to hash file paths into the index
+///
+/// The algorithm id is stored in the options of a v3 index header, v1 and v2 indexes always use fnv1a
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum HashAlgorithm {
+    /// 64 bit FNV-1a, compatible with other cotar implementations
+    #[default]
+    Fnv1a,
+    /// 64 bit xxHash3
+    Xxh3,
+    /// SipHash-2-4 with a zero key
+    SipHash,
+}
+
+impl HashAlgorithm {
+    /// Id of the algorithm stored in the index header
+    pub fn id(&self) -> u8 {
+        match self {
+            HashAlgorithm::Fnv1a => 0,
+            HashAlgorithm::Xxh3 => 1,
+            HashAlgorithm::SipHash => 2,
+        }
+    }
+
+    /// Algorithm from the id stored in the index header
+    pub fn from_id(id: u8) -> CoreResult<Self> {
+        match id {
+            0 => Ok(HashAlgorithm::Fnv1a),
+            1 => Ok(HashAlgorithm::Xxh3),
+            2 => Ok(HashAlgorithm::SipHash),
+            _ => Err(CoreError::UnsupportedHashAlgorithm(id)),
+        }
+    }
+
+    /// Hash a file path
+    pub fn hash(&self, path: &[u8]) -> u64 {
+        match self {
+            HashAlgorithm::Fnv1a => fnv1a_64(path),
+            HashAlgorithm::Xxh3 => xxh3_64(path),
+            HashAlgorithm::SipHash => SipHasher24::new().hash(path),
+        }
+    }
+}
+
+impl fmt::Display for HashAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HashAlgorithm::Fnv1a => write!(f, "fnv1a"),
+            HashAlgorithm::Xxh3 => write!(f, "xxh3"),
+            HashAlgorithm::SipHash => write!(f, "siphash"),
+        }
+    }
+}
+
+impl FromStr for HashAlgorithm {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "fnv1a" => Ok(HashAlgorithm::Fnv1a),
+            "xxh3" => Ok(HashAlgorithm::Xxh3),
+            "siphash" => Ok(HashAlgorithm::SipHash),
+            _ => Err(format!("Unknown hash algorithm: {}", s)),
+        }
+    }
+}
+
+#[test]
+fn test_hash_algorithms() {
+    for algorithm in [
+        HashAlgorithm::Fnv1a,
+        HashAlgorithm::Xxh3,
+        HashAlgorithm::SipHash,
+    ] {
+        assert_eq!(HashAlgorithm::from_id(algorithm.id()).unwrap(), algorithm);
+        assert_eq!(
+            algorithm.to_string().parse::<HashAlgorithm>(),
+            Ok(algorithm)
+        );
+    }
+    assert_eq!(
+        HashAlgorithm::from_id(3),
+        Err(CoreError::UnsupportedHashAlgorithm(3))
+    );
+
+    assert_eq!(HashAlgorithm::default().hash(b"hello"), 0xa430d84680aabd0b);
+    assert_eq!(HashAlgorithm::Xxh3.hash(b"hello"), 0x9555e8555c62dcfd);
+    assert_eq!(HashAlgorithm::SipHash.hash(b"hello"), 0x8cc15d5db2f752b9);
+}
@@ -0,0 +1,232 @@
+use crate::error::{CoreError, CoreResult};
+use crate::hash::HashAlgorithm;
+
+/// V2 cotar files have a fixed header and entry size
+pub const COTAR_V2_HEADER_SIZE: u64 = 8;
+pub const COTAR_V2_INDEX_ENTRY_SIZE: u64 = 16;
+
+/// "COT\x02" as a u32
+pub const COTAR_V2_HEADER_MAGIC: u32 = 39079747;
+
+/// V1 cotar files use the same header as v2 with larger entries
+///
+/// Entries are the hash, file offset in bytes and file size all as u64
+pub const COTAR_V1_INDEX_ENTRY_SIZE: u64 = 24;
+
+/// "COT\x01" as a u32
+pub const COTAR_V1_HEADER_MAGIC: u32 = 22302531;
+
+/// V3 cotar files have 64 bit entries for tars larger than 2TiB or files larger than 4GiB
+///
+/// The header is the magic, slot count and 8 bytes of index options, the footer stores the options first
+/// so the magic and slot count are always the first and final 8 bytes of the index
+pub const COTAR_V3_HEADER_SIZE: u64 = 16;
+
+/// Entries are the hash, file offset in bytes and file size all as u64
+pub const COTAR_V3_INDEX_ENTRY_SIZE: u64 = 24;
+
+/// "COT\x03" as a u32
+pub const COTAR_V3_HEADER_MAGIC: u32 = 55856963;
+
+/// Size of a index entry for a cotar version
+pub fn index_entry_size(version: u8) -> u64 {
+    match version {
+        1 => COTAR_V1_INDEX_ENTRY_SIZE,
+        3 => COTAR_V3_INDEX_ENTRY_SIZE,
+        _ => COTAR_V2_INDEX_ENTRY_SIZE,
+    }
+}
+
+/// Size of the index header and footer for a cotar version
+pub fn header_size(version: u8) -> u64 {
+    match version {
+        3 => COTAR_V3_HEADER_SIZE,
+        _ => COTAR_V2_HEADER_SIZE,
+    }
+}
+
+/// "COT" followed by the version as a u32
+pub fn header_magic(version: u8) -> u32 {
+    (COTAR_V2_HEADER_MAGIC & 0x00ff_ffff) | ((version as u32) << 24)
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    let mut buf = [0; 4];
+    buf.copy_from_slice(&bytes[offset..offset + 4]);
+    u32::from_le_bytes(buf)
+}
+
+fn read_u64(bytes: &[u8], offset: usize) -> u64 {
+    let mut buf = [0; 8];
+    buf.copy_from_slice(&bytes[offset..offset + 8]);
+    u64::from_le_bytes(buf)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CotarIndexEntry {
+    /// Hash of the file path
+    pub hash: u64,
+    /// File offset
+    pub file_offset: u64,
+    /// File size
+    pub file_size: u64,
+}
+
+impl CotarIndexEntry {
+    /// Decode a slot of a index
+    ///
+    /// Returns None if the slot is empty
+    pub fn from_slot(bytes: &[u8], version: u8) -> CoreResult<Option<Self>> {
+        if (bytes.len() as u64) < index_entry_size(version) {
+            return Err(CoreError::TruncatedIndex);
+        }
+        let hash = read_u64(bytes, 0);
+        // Null entry slot is empty
+        if hash == 0 {
+            return Ok(None);
+        }
+        let (file_offset, file_size) = match version {
+            1 | 3 => (read_u64(bytes, 8), read_u64(bytes, 16)),
+            // Tar files are aligned to 512 byte blocks so v2 stores the block offset
+            _ => (
+                (read_u32(bytes, 8) as u64) * 512,
+                read_u32(bytes, 12) as u64,
+            ),
+        };
+        Ok(Some(CotarIndexEntry {
+            hash,
+            file_offset,
+            file_size,
+        }))
+    }
+
+    /// Ensure the file is inside the first `size` bytes of the tar
+    pub fn check_bounds(&self, size: u64) -> CoreResult<()> {
+        let end = self.file_offset.checked_add(self.file_size);
+        match end {
+            Some(end) if end <= size => Ok(()),
+            _ => Err(CoreError::OutOfBounds {
+                file_offset: self.file_offset,
+                file_size: self.file_size,
+            }),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CotarHeader {
+    /// Cotar magic generally "COT\x02"
+    pub magic: u32,
+    /// Cotar index version generally v2
+    pub version: u8,
+    /// Number of entries in the tar archive
+    pub entries: u32,
+    /// Algorithm used to hash file paths, stored in the options of v3 headers
+    pub hash_algorithm: HashAlgorithm,
+}
+
+impl CotarHeader {
+    pub fn new(version: u8, entries: u32, hash_algorithm: HashAlgorithm) -> Self {
+        CotarHeader {
+            magic: header_magic(version),
+            version,
+            entries,
+            hash_algorithm,
+        }
+    }
+
+    /// Parse the magic and slot count of a index header
+    ///
+    /// v3 index options are not read, see `from_index_start` and `from_index_end`
+    pub fn from_bytes(bytes: &[u8]) -> CoreResult<Self> {
+        if bytes.len() < COTAR_V2_HEADER_SIZE as usize {
+            return Err(CoreError::TruncatedIndex);
+        }
+        let magic = read_u32(bytes, 0);
+        // "COT" followed by the version
+        if magic & 0x00ff_ffff != COTAR_V2_HEADER_MAGIC & 0x00ff_ffff {
+            return Err(CoreError::InvalidMagic);
+        }
+        let version = (magic >> 24) as u8;
+        if !(1..=3).contains(&version) {
+            return Err(CoreError::UnsupportedVersion(version));
+        }
+
+        let entries = read_u32(bytes, 4);
+
+        Ok(CotarHeader {
+            magic,
+            version,
+            entries,
+            hash_algorithm: HashAlgorithm::Fnv1a,
+        })
+    }
+
+    /// Parse the header from the start of a index
+    ///
+    /// `bytes` are the first bytes of the index, up to `COTAR_V3_HEADER_SIZE` bytes are needed for v3 options
+    pub fn from_index_start(bytes: &[u8]) -> CoreResult<Self> {
+        let split = bytes.len().min(COTAR_V2_HEADER_SIZE as usize);
+        CotarHeader::from_bytes(&bytes[..split])?.read_options(&bytes[split..])
+    }
+
+    /// Parse the header from the end of a index
+    ///
+    /// `bytes` are the last bytes of the index, up to `COTAR_V3_HEADER_SIZE` bytes are needed for v3 options
+    pub fn from_index_end(bytes: &[u8]) -> CoreResult<Self> {
+        let split = bytes.len().saturating_sub(COTAR_V2_HEADER_SIZE as usize);
+        CotarHeader::from_bytes(&bytes[split..])?.read_options(&bytes[..split])
+    }
+
+    /// Read the v3 index options that are stored next to the magic and slot count
+    fn read_options(mut self, options: &[u8]) -> CoreResult<Self> {
+        if self.version != 3 {
+            return Ok(self);
+        }
+        let options_size = (COTAR_V3_HEADER_SIZE - COTAR_V2_HEADER_SIZE) as usize;
+        if options.len() < options_size {
+            return Err(CoreError::TruncatedIndex);
+        }
+        let options = &options[options.len() - options_size..];
+        self.hash_algorithm = HashAlgorithm::from_id(options[0])?;
+        Ok(self)
+    }
+
+    /// Size of the index in bytes including the header and footer
+    pub fn index_size(&self) -> u64 {
+        header_size(self.version) * 2 + (self.entries as u64) * index_entry_size(self.version)
+    }
+
+    /// Offset of the index when it is the final bytes of a source of `size` bytes
+    pub fn embedded_index_offset(&self, size: u64) -> CoreResult<u64> {
+        size.checked_sub(self.index_size())
+            .ok_or(CoreError::TruncatedIndex)
+    }
+
+    /// Offset of a slot from the start of the index
+    pub fn slot_offset(&self, slot: u64) -> u64 {
+        slot * index_entry_size(self.version) + header_size(self.version)
+    }
+
+    /// Ensure the index bytes start and end with this header
+    pub fn check_index(&self, index: &[u8]) -> CoreResult<()> {
+        let size = self.index_size();
+        if index.len() as u64 != size {
+            return Err(CoreError::TruncatedIndex);
+        }
+        let header_size = header_size(self.version) as usize;
+        let footer_offset = index.len() - header_size;
+        for header in [
+            CotarHeader::from_index_start(&index[..header_size])?,
+            CotarHeader::from_index_end(&index[footer_offset..])?,
+        ] {
+            if header.version != self.version
+                || header.entries != self.entries
+                || header.hash_algorithm != self.hash_algorithm
+            {
+                return Err(CoreError::TruncatedIndex);
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,18 @@
+//! `no_std` core of cotar, parsing and searching cotar indexes over byte slices or a `ReadAt`
+#![cfg_attr(not(test), no_std)]
+
+extern crate alloc;
+
+mod error;
+mod fnv1a;
+mod hash;
+mod index;
+mod probe;
+mod read;
+
+pub use crate::error::*;
+pub use crate::fnv1a::*;
+pub use crate::hash::HashAlgorithm;
+pub use crate::index::*;
+pub use crate::probe::{Lookup, Probe, ProbeResult, ProbeStep};
+pub use crate::read::{Index, ReadAt};
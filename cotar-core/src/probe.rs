@@ -0,0 +1,196 @@
+use crate::error::CoreResult;
+use crate::index::{header_size, index_entry_size, CotarIndexEntry};
+
+/// Outcome of checking one slot of the hash index
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProbeStep {
+    /// Slot contains the hash
+    Found(CotarIndexEntry),
+    /// Hash is not in the index
+    Missing,
+    /// Slot is used by another hash, check the next slot
+    Next,
+}
+
+/// Result of probing the hash index for a hash
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProbeResult {
+    /// Hash that was searched for
+    pub hash: u64,
+    /// Entry with the hash, None if the hash is not in the index
+    pub entry: Option<CotarIndexEntry>,
+    /// Slot the entry was found in, or the last slot checked if the hash is not in the index
+    pub slot: u64,
+    /// Number of slots `slot` is past the slot the hash starts probing from
+    pub distance: u64,
+}
+
+/// Linear probe through the hash index looking for a hash
+///
+/// The caller reads the slot at `offset()` and feeds it to `step()` until the probe completes,
+/// keeping the probing logic independent of how the index is read
+#[derive(Debug, Clone)]
+pub struct Probe {
+    hash: u64,
+    entries: u64,
+    version: u8,
+    start_index: u64,
+    index: u64,
+}
+
+impl Probe {
+    /// Start probing for `hash` in a index with `entries` slots
+    ///
+    /// Returns None if `entries` is 0 as a index without slots cannot contain the hash
+    pub fn new(hash: u64, entries: u64, version: u8) -> Option<Self> {
+        let start_index = hash.checked_rem(entries)?;
+        Some(Probe {
+            hash,
+            entries,
+            version,
+            start_index,
+            index: start_index,
+        })
+    }
+
+    /// Index of the current slot
+    pub fn slot(&self) -> u64 {
+        self.index
+    }
+
+    /// Number of slots the current slot is past the slot the probe started at
+    pub fn distance(&self) -> u64 {
+        (self.index + self.entries - self.start_index) % self.entries
+    }
+
+    /// Offset of the current slot from the start of the index
+    pub fn offset(&self) -> u64 {
+        self.index * index_entry_size(self.version) + header_size(self.version)
+    }
+
+    /// Check the bytes of the current slot and move to the next slot if needed
+    pub fn step(&mut self, bytes: &[u8]) -> CoreResult<ProbeStep> {
+        let entry = match CotarIndexEntry::from_slot(bytes, self.version)? {
+            // Null entry file is missing
+            None => return Ok(ProbeStep::Missing),
+            Some(entry) => entry,
+        };
+        if entry.hash == self.hash {
+            return Ok(ProbeStep::Found(entry));
+        }
+
+        match self.next_slot() {
+            true => Ok(ProbeStep::Next),
+            false => Ok(ProbeStep::Missing),
+        }
+    }
+
+    /// Move to the next slot, returns false once every slot has been checked
+    pub fn next_slot(&mut self) -> bool {
+        self.index += 1;
+        // Loop around to the start of the hash table
+        if self.index >= self.entries {
+            self.index = 0;
+        }
+        // Looped full around nothing to find here
+        self.index != self.start_index
+    }
+}
+
+/// Lookup of a hash in a index, checking each entry found is inside the tar
+///
+/// Wraps a `Probe` so every reader shares the probing logic, the caller reads the slot at
+/// `offset()` and feeds it to `step()` until `offset()` returns None, then takes the `result()`
+#[derive(Debug, Clone)]
+pub struct Lookup {
+    /// None if the index does not have any slots
+    probe: Option<Probe>,
+    data_size: u64,
+    result: ProbeResult,
+    probes: u64,
+    complete: bool,
+}
+
+impl Lookup {
+    /// Look up `hash` in a index with `entries` slots, entries must end within the first
+    /// `data_size` bytes of the tar
+    pub fn new(hash: u64, entries: u64, version: u8, data_size: u64) -> Self {
+        let probe = Probe::new(hash, entries, version);
+        Lookup {
+            result: ProbeResult {
+                hash,
+                entry: None,
+                slot: probe.as_ref().map_or(0, Probe::slot),
+                distance: 0,
+            },
+            complete: probe.is_none(),
+            probe,
+            data_size,
+            probes: 0,
+        }
+    }
+
+    /// Offset from the start of the index of the next slot to read, None once the lookup is complete
+    pub fn offset(&self) -> Option<u64> {
+        match (&self.probe, self.complete) {
+            (Some(probe), false) => Some(probe.offset()),
+            _ => None,
+        }
+    }
+
+    /// Check the bytes of the slot at `offset()`
+    ///
+    /// Returns the entry if the slot contains the hash, which completes the lookup unless the
+    /// entry is passed over with `reject`. Entries that point outside of the tar return a
+    /// `OutOfBounds` error
+    pub fn step(&mut self, bytes: &[u8]) -> CoreResult<Option<CotarIndexEntry>> {
+        let probe = match &mut self.probe {
+            Some(probe) if !self.complete => probe,
+            _ => return Ok(None),
+        };
+        self.probes += 1;
+        self.result.slot = probe.slot();
+        self.result.distance = probe.distance();
+        match probe.step(bytes)? {
+            ProbeStep::Found(entry) => {
+                self.complete = true;
+                entry.check_bounds(self.data_size)?;
+                self.result.entry = Some(entry);
+                Ok(Some(entry))
+            }
+            ProbeStep::Missing => {
+                self.complete = true;
+                Ok(None)
+            }
+            ProbeStep::Next => Ok(None),
+        }
+    }
+
+    /// Pass over the entry returned by the last `step` as it belongs to another path with the
+    /// same hash, and keep probing in case the hash is in the index more than once
+    pub fn reject(&mut self) {
+        if let (Some(probe), Some(_)) = (&mut self.probe, self.result.entry.take()) {
+            self.complete = !probe.next_slot();
+        }
+    }
+
+    /// Number of slots checked so far
+    pub fn probes(&self) -> u64 {
+        self.probes
+    }
+
+    /// Entry found and where the lookup stopped
+    pub fn result(&self) -> ProbeResult {
+        self.result
+    }
+}
+
+#[test]
+fn test_probe_empty_index() {
+    assert!(Probe::new(1, 0, 2).is_none());
+
+    let mut probe = Probe::new(5, 4, 2).unwrap();
+    assert_eq!(probe.slot(), 1);
+    assert!(probe.next_slot());
+    assert_eq!(probe.distance(), 1);
+}
@@ -0,0 +1,277 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::error::{CoreError, CoreResult};
+use crate::hash::HashAlgorithm;
+use crate::index::{
+    index_entry_size, CotarHeader, CotarIndexEntry, COTAR_V2_HEADER_SIZE, COTAR_V3_HEADER_SIZE,
+};
+use crate::probe::{Lookup, ProbeResult};
+
+/// Minimal positional read trait for the index and tar of a archive
+///
+/// Errors from the index are converted into the error type of the reader
+pub trait ReadAt {
+    type Error: From<CoreError>;
+
+    /// Total size in bytes
+    fn size(&self) -> u64;
+
+    /// Fill `buf` with the bytes starting at `offset`
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> Result<(), Self::Error>;
+}
+
+impl ReadAt for [u8] {
+    type Error = CoreError;
+
+    fn size(&self) -> u64 {
+        self.len() as u64
+    }
+
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> Result<(), CoreError> {
+        let len = buf.len() as u64;
+        let bytes = usize::try_from(offset)
+            .ok()
+            .and_then(|start| self.get(start..start.checked_add(buf.len())?))
+            .ok_or(CoreError::UnexpectedEof { offset, len })?;
+        buf.copy_from_slice(bytes);
+        Ok(())
+    }
+}
+
+impl<R: ReadAt + ?Sized> ReadAt for &R {
+    type Error = R::Error;
+
+    fn size(&self) -> u64 {
+        (**self).size()
+    }
+
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> Result<(), Self::Error> {
+        (**self).read_at(offset, buf)
+    }
+}
+
+impl ReadAt for Vec<u8> {
+    type Error = CoreError;
+
+    fn size(&self) -> u64 {
+        self.as_slice().size()
+    }
+
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> Result<(), CoreError> {
+        self.as_slice().read_at(offset, buf)
+    }
+}
+
+/// Location of a hash index and the tar it describes
+///
+/// Lookups read one slot at a time from a `ReadAt`, the whole index can be read with `load`
+/// and searched as a byte slice
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Index {
+    pub header: CotarHeader,
+    /// Offset of the index in its reader
+    pub index_offset: u64,
+    /// Number of bytes at the start of the tar that files can be stored in
+    pub data_size: u64,
+}
+
+impl Index {
+    /// Find the index at the end of a tar
+    pub fn open_embedded<R: ReadAt + ?Sized>(source: &R) -> Result<Self, R::Error> {
+        let size = source.size();
+        if size < COTAR_V2_HEADER_SIZE {
+            return Err(CoreError::TruncatedIndex.into());
+        }
+        // Read enough of the footer for v3 index options
+        let mut footer = [0; COTAR_V3_HEADER_SIZE as usize];
+        let footer = &mut footer[..size.min(COTAR_V3_HEADER_SIZE) as usize];
+        source.read_at(size - footer.len() as u64, footer)?;
+        Ok(Index::from_tail(footer, size)?)
+    }
+
+    /// Parse the index at the end of a tar of `size` bytes from the final bytes of the tar
+    ///
+    /// Up to `COTAR_V3_HEADER_SIZE` bytes are needed for v3 index options
+    pub fn from_tail(tail: &[u8], size: u64) -> CoreResult<Self> {
+        let header = CotarHeader::from_index_end(tail)?;
+        let index_offset = header.embedded_index_offset(size)?;
+        Ok(Index {
+            header,
+            index_offset,
+            data_size: index_offset,
+        })
+    }
+
+    /// Read a index stored separately from a tar of `data_size` bytes
+    pub fn open_separate<R: ReadAt + ?Sized>(index: &R, data_size: u64) -> Result<Self, R::Error> {
+        let size = index.size();
+        if size < COTAR_V2_HEADER_SIZE {
+            return Err(CoreError::TruncatedIndex.into());
+        }
+        let mut header = [0; COTAR_V3_HEADER_SIZE as usize];
+        let header = &mut header[..size.min(COTAR_V3_HEADER_SIZE) as usize];
+        index.read_at(0, header)?;
+        Ok(Index::from_head(header, size, data_size)?)
+    }
+
+    /// Parse a index of `size` bytes stored separately from a tar of `data_size` bytes
+    /// from the first bytes of the index
+    ///
+    /// Up to `COTAR_V3_HEADER_SIZE` bytes are needed for v3 index options
+    pub fn from_head(head: &[u8], size: u64, data_size: u64) -> CoreResult<Self> {
+        let header = CotarHeader::from_index_start(head)?;
+        if size < header.index_size() {
+            return Err(CoreError::TruncatedIndex);
+        }
+        Ok(Index {
+            header,
+            index_offset: 0,
+            data_size,
+        })
+    }
+
+    /// Number of slots in the index
+    pub fn entries(&self) -> u64 {
+        self.header.entries as u64
+    }
+
+    /// Algorithm used to hash file paths
+    pub fn hash_algorithm(&self) -> HashAlgorithm {
+        self.header.hash_algorithm
+    }
+
+    /// Read the entire index, checking its header and footer match
+    pub fn load<R: ReadAt + ?Sized>(&self, index: &R) -> Result<Vec<u8>, R::Error> {
+        let mut bytes = vec![0; self.header.index_size() as usize];
+        index.read_at(self.index_offset, &mut bytes)?;
+        self.header.check_index(&bytes)?;
+        Ok(bytes)
+    }
+
+    /// Find the entry for a file path
+    ///
+    /// Returns None if the file is not found
+    pub fn info<R: ReadAt + ?Sized>(
+        &self,
+        index: &R,
+        path: &str,
+    ) -> Result<Option<CotarIndexEntry>, R::Error> {
+        self.find(index, self.hash_algorithm().hash(path.as_bytes()))
+    }
+
+    /// Find the entry for a path hash
+    ///
+    /// Returns None if the hash is not found, entries that point outside of the tar return a
+    /// `OutOfBounds` error
+    pub fn find<R: ReadAt + ?Sized>(
+        &self,
+        index: &R,
+        hash: u64,
+    ) -> Result<Option<CotarIndexEntry>, R::Error> {
+        Ok(self.probe(index, hash)?.entry)
+    }
+
+    /// Probe the index for a path hash, reporting the slot and probe distance of the entry
+    ///
+    /// Indexes without any slots report slot 0
+    pub fn probe<R: ReadAt + ?Sized>(&self, index: &R, hash: u64) -> Result<ProbeResult, R::Error> {
+        let mut lookup = self.lookup(hash);
+        let mut slot = [0; 24];
+        let slot = &mut slot[..index_entry_size(self.header.version) as usize];
+        while let Some(offset) = lookup.offset() {
+            index.read_at(self.index_offset + offset, slot)?;
+            lookup.step(slot)?;
+        }
+        Ok(lookup.result())
+    }
+
+    /// Start a lookup of a path hash that is driven by the caller reading each slot
+    pub fn lookup(&self, hash: u64) -> Lookup {
+        Lookup::new(hash, self.entries(), self.header.version, self.data_size)
+    }
+
+    /// Offset from the start of the index of a slot, None if the index does not have the slot
+    pub fn slot_offset(&self, slot: u64) -> Option<u64> {
+        (slot < self.entries()).then(|| self.header.slot_offset(slot))
+    }
+
+    /// Decode the bytes of a slot
+    ///
+    /// Returns None if the slot is empty, entries that point outside of the tar return a
+    /// `OutOfBounds` error
+    pub fn entry(&self, bytes: &[u8]) -> CoreResult<Option<CotarIndexEntry>> {
+        let entry = CotarIndexEntry::from_slot(bytes, self.header.version)?;
+        if let Some(entry) = &entry {
+            entry.check_bounds(self.data_size)?;
+        }
+        Ok(entry)
+    }
+}
+
+#[test]
+fn test_index_lookup() {
+    use crate::index::{header_magic, header_size};
+
+    // Tar of a single file at 512 with a v2 index of 2 slots embedded after it
+    let hash = HashAlgorithm::Fnv1a.hash(b"a.txt");
+    let mut tar = vec![0; 1024];
+    tar[512..516].copy_from_slice(b"data");
+    let mut index = Vec::new();
+    index.extend_from_slice(&header_magic(2).to_le_bytes());
+    index.extend_from_slice(&2u32.to_le_bytes());
+    let mut slots = [[0; 16]; 2];
+    let slot = &mut slots[(hash % 2) as usize];
+    slot[..8].copy_from_slice(&hash.to_le_bytes());
+    slot[8..12].copy_from_slice(&1u32.to_le_bytes());
+    slot[12..].copy_from_slice(&4u32.to_le_bytes());
+    index.extend(slots.iter().flatten());
+    index.extend_from_within(..header_size(2) as usize);
+
+    let mut embedded = tar.clone();
+    embedded.extend_from_slice(&index);
+    let cotar = Index::open_embedded(embedded.as_slice()).unwrap();
+    assert_eq!(cotar.entries(), 2);
+    assert_eq!(cotar.index_offset, 1024);
+    let entry = cotar.info(&embedded, "a.txt").unwrap().unwrap();
+    assert_eq!((entry.file_offset, entry.file_size), (512, 4));
+    assert_eq!(cotar.info(&embedded, "b.txt").unwrap(), None);
+
+    // Loaded indexes are searched as a byte slice
+    let loaded = cotar.load(&embedded).unwrap();
+    let separate = Index::open_separate(loaded.as_slice(), tar.size()).unwrap();
+    assert_eq!(separate.find(&loaded, hash).unwrap(), Some(entry));
+
+    // The probe reports where the entry was found
+    let probe = separate.probe(&loaded, hash).unwrap();
+    assert_eq!((probe.slot, probe.distance), (hash % 2, 0));
+    assert_eq!(probe.entry, Some(entry));
+
+    // Lookups read each slot through the caller, a rejected entry keeps probing
+    let mut lookup = separate.lookup(hash);
+    let slot = |offset: u64| &loaded[offset as usize..][..16];
+    let offset = lookup.offset().unwrap();
+    assert_eq!(lookup.step(slot(offset)).unwrap(), Some(entry));
+    assert_eq!(lookup.offset(), None);
+    lookup.reject();
+    while let Some(offset) = lookup.offset() {
+        assert_eq!(lookup.step(slot(offset)).unwrap(), None);
+    }
+    assert_eq!(lookup.result().entry, None);
+    assert_eq!(lookup.probes(), 2);
+
+    // Entries outside of the tar are rejected
+    let small = Index::open_separate(loaded.as_slice(), 515).unwrap();
+    assert!(matches!(
+        small.find(&loaded, hash),
+        Err(CoreError::OutOfBounds { .. })
+    ));
+    assert_eq!(
+        Index::open_embedded(&embedded[..4]),
+        Err(CoreError::TruncatedIndex)
+    );
+    assert_eq!(
+        Index::open_separate(&loaded[..loaded.len() - 1], 1024),
+        Err(CoreError::TruncatedIndex)
+    );
+}
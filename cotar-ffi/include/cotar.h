@@ -0,0 +1,120 @@
+#ifndef COTAR_H
+#define COTAR_H
+
+/* Generated with cbindgen from cotar-ffi, do not edit. Regenerate with `COTAR_UPDATE_HEADER=1 cargo test -p cotar-ffi --test header` */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// Result of a cotar function
+typedef enum CotarStatus {
+  // Success
+  COTAR_STATUS_OK = 0,
+  // File is not in the archive
+  COTAR_STATUS_NOT_FOUND = 1,
+  // Output buffer is smaller than the file, the required size is written to `out_len`
+  COTAR_STATUS_BUFFER_TOO_SMALL = 2,
+  // Null pointer or a path that is not UTF-8
+  COTAR_STATUS_INVALID_ARGUMENT = -1,
+  // Reading the archive failed
+  COTAR_STATUS_IO = -2,
+  // Archive or index is corrupt or not supported
+  COTAR_STATUS_INVALID_ARCHIVE = -3,
+  // The library panicked, the panic message is available from `cotar_last_error`
+  COTAR_STATUS_PANIC = -4,
+} CotarStatus;
+
+// Opaque handle to a open archive, created by `cotar_open` and freed by `cotar_close`
+typedef struct CotarHandle CotarHandle;
+
+// Location of a file in the tar
+typedef struct CotarEntry {
+  // Hash of the file path
+  uint64_t hash;
+  // Offset of the file in the tar
+  uint64_t file_offset;
+  // Size of the file in bytes
+  uint64_t file_size;
+} CotarEntry;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Open a tar with the index appended to the end of it
+//
+// # Safety
+// `path` must be a nul terminated string and `out` valid for writes,
+// the handle written to `out` must be freed with `cotar_close`
+enum CotarStatus cotar_open(const char *path, struct CotarHandle **out);
+
+// Open a tar with a separate index file
+//
+// # Safety
+// `tar_path` and `index_path` must be nul terminated strings and `out` valid for writes,
+// the handle written to `out` must be freed with `cotar_close`
+enum CotarStatus cotar_open_index(const char *tar_path,
+                                  const char *index_path,
+                                  struct CotarHandle **out);
+
+// Free a handle from `cotar_open`, null is ignored
+//
+// # Safety
+// `cotar` must be null or a handle from `cotar_open` that has not been closed
+void cotar_close(struct CotarHandle *cotar);
+
+// Find the location of a file in the tar
+//
+// # Safety
+// `cotar` must be a open handle, `path` a nul terminated string and `out` valid for writes
+enum CotarStatus cotar_info(const struct CotarHandle *cotar,
+                            const char *path,
+                            struct CotarEntry *out);
+
+// Read a file into a caller owned buffer
+//
+// The size of the file is written to `out_len`, if `buf_len` is too small nothing is copied and
+// `BufferTooSmall` is returned, so `buf` can be null to query the size of a file
+//
+// # Safety
+// `cotar` must be a open handle, `path` a nul terminated string, `buf` valid for `buf_len` bytes
+// of writes and `out_len` valid for writes
+enum CotarStatus cotar_get(const struct CotarHandle *cotar,
+                           const char *path,
+                           uint8_t *buf,
+                           size_t buf_len,
+                           size_t *out_len);
+
+// Read a file into a buffer allocated by the library
+//
+// The buffer written to `out` must be freed with `cotar_free` with the length from `out_len`
+//
+// # Safety
+// `cotar` must be a open handle, `path` a nul terminated string, `out` and `out_len` valid for writes
+enum CotarStatus cotar_get_owned(const struct CotarHandle *cotar,
+                                 const char *path,
+                                 uint8_t **out,
+                                 size_t *out_len);
+
+// Free a buffer from `cotar_get_owned`, null is ignored
+//
+// # Safety
+// `buf` must be null or a buffer from `cotar_get_owned` with its length `len`
+void cotar_free(uint8_t *buf, size_t len);
+
+// Static description of a status
+const char *cotar_status_string(int32_t status);
+
+// Message of the last error on the current thread, or null if there has been no error
+//
+// The string is valid until the next error on the same thread
+const char *cotar_last_error(void);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* COTAR_H */
@@ -0,0 +1,350 @@
+//! C ABI for reading cotar archives
+//!
+//! Functions return a `CotarStatus`, details of the last error on the current thread are available
+//! from `cotar_last_error`. The header `include/cotar.h` is generated with cbindgen.
+use std::any::Any;
+use std::cell::RefCell;
+use std::ffi::{c_char, CStr, CString};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+
+use bytes::Bytes;
+use cotar::{Cotar, CotarError, CotarIndexEntry};
+
+/// Result of a cotar function
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CotarStatus {
+    /// Success
+    Ok = 0,
+    /// File is not in the archive
+    NotFound = 1,
+    /// Output buffer is smaller than the file, the required size is written to `out_len`
+    BufferTooSmall = 2,
+    /// Null pointer or a path that is not UTF-8
+    InvalidArgument = -1,
+    /// Reading the archive failed
+    Io = -2,
+    /// Archive or index is corrupt or not supported
+    InvalidArchive = -3,
+    /// The library panicked, the panic message is available from `cotar_last_error`
+    Panic = -4,
+}
+
+/// Opaque handle to a open archive, created by `cotar_open` and freed by `cotar_close`
+pub struct CotarHandle(Cotar);
+
+/// Location of a file in the tar
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CotarEntry {
+    /// Hash of the file path
+    pub hash: u64,
+    /// Offset of the file in the tar
+    pub file_offset: u64,
+    /// Size of the file in bytes
+    pub file_size: u64,
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_last_error(message: String) {
+    let message = CString::new(message.replace('\0', " ")).unwrap_or_default();
+    LAST_ERROR.with(|last| *last.borrow_mut() = Some(message));
+}
+
+/// Record a error as the last error and convert it to a status
+fn error_status(e: CotarError) -> CotarStatus {
+    let status = match e {
+        CotarError::Io(_) => CotarStatus::Io,
+        _ => CotarStatus::InvalidArchive,
+    };
+    set_last_error(e.to_string());
+    status
+}
+
+/// Run the body of a exported function so a panic never unwinds into C
+///
+/// Panics are recorded as the last error and returned as `Panic`
+fn catch_panic(body: impl FnOnce() -> CotarStatus) -> CotarStatus {
+    panic::catch_unwind(AssertUnwindSafe(body)).unwrap_or_else(|payload| {
+        set_last_error(panic_message(payload));
+        CotarStatus::Panic
+    })
+}
+
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    let message = match payload.downcast::<String>() {
+        Ok(message) => *message,
+        Err(payload) => match payload.downcast::<&str>() {
+            Ok(message) => message.to_string(),
+            Err(_) => String::from("unknown panic"),
+        },
+    };
+    format!("panic: {}", message)
+}
+
+fn invalid_argument(message: &str) -> CotarStatus {
+    set_last_error(message.to_string());
+    CotarStatus::InvalidArgument
+}
+
+/// Read a UTF-8 string from a C string
+///
+/// # Safety
+/// `value` must be null or a valid nul terminated string
+unsafe fn c_str<'a>(value: *const c_char, name: &str) -> Result<&'a str, CotarStatus> {
+    if value.is_null() {
+        return Err(invalid_argument(&format!("{} is null", name)));
+    }
+    CStr::from_ptr(value)
+        .to_str()
+        .map_err(|_| invalid_argument(&format!("{} is not UTF-8", name)))
+}
+
+/// Store a opened archive in `out`
+///
+/// # Safety
+/// `out` must be null or valid for writes
+unsafe fn open_with(
+    out: *mut *mut CotarHandle,
+    open: impl FnOnce() -> Result<Cotar, CotarStatus>,
+) -> CotarStatus {
+    if out.is_null() {
+        return invalid_argument("out is null");
+    }
+    *out = ptr::null_mut();
+    match open() {
+        Ok(cotar) => {
+            *out = Box::into_raw(Box::new(CotarHandle(cotar)));
+            CotarStatus::Ok
+        }
+        Err(status) => status,
+    }
+}
+
+/// Open a tar with the index appended to the end of it
+///
+/// # Safety
+/// `path` must be a nul terminated string and `out` valid for writes,
+/// the handle written to `out` must be freed with `cotar_close`
+#[no_mangle]
+pub unsafe extern "C" fn cotar_open(
+    path: *const c_char,
+    out: *mut *mut CotarHandle,
+) -> CotarStatus {
+    catch_panic(|| {
+        open_with(out, || {
+            let path = c_str(path, "path")?;
+            Cotar::from_tar(path).map_err(error_status)
+        })
+    })
+}
+
+/// Open a tar with a separate index file
+///
+/// # Safety
+/// `tar_path` and `index_path` must be nul terminated strings and `out` valid for writes,
+/// the handle written to `out` must be freed with `cotar_close`
+#[no_mangle]
+pub unsafe extern "C" fn cotar_open_index(
+    tar_path: *const c_char,
+    index_path: *const c_char,
+    out: *mut *mut CotarHandle,
+) -> CotarStatus {
+    catch_panic(|| {
+        open_with(out, || {
+            let tar_path = c_str(tar_path, "tar_path")?;
+            let index_path = c_str(index_path, "index_path")?;
+            Cotar::from_tar_index(tar_path, index_path).map_err(error_status)
+        })
+    })
+}
+
+/// Free a handle from `cotar_open`, null is ignored
+///
+/// # Safety
+/// `cotar` must be null or a handle from `cotar_open` that has not been closed
+#[no_mangle]
+pub unsafe extern "C" fn cotar_close(cotar: *mut CotarHandle) {
+    catch_panic(|| {
+        if !cotar.is_null() {
+            drop(Box::from_raw(cotar));
+        }
+        CotarStatus::Ok
+    });
+}
+
+/// Look up a file in the index
+///
+/// # Safety
+/// `cotar` must be null or a open handle and `path` null or a nul terminated string
+unsafe fn lookup(
+    cotar: *const CotarHandle,
+    path: *const c_char,
+) -> Result<Option<CotarIndexEntry>, CotarStatus> {
+    let cotar = cotar
+        .as_ref()
+        .ok_or_else(|| invalid_argument("cotar is null"))?;
+    let path = c_str(path, "path")?;
+    cotar.0.info(path).map_err(error_status)
+}
+
+/// Read a file with `Cotar::get` so it is checked the same way as in Rust
+///
+/// # Safety
+/// `cotar` must be null or a open handle and `path` null or a nul terminated string
+unsafe fn read_file(cotar: *const CotarHandle, path: *const c_char) -> Result<Bytes, CotarStatus> {
+    let cotar = cotar
+        .as_ref()
+        .ok_or_else(|| invalid_argument("cotar is null"))?;
+    let path = c_str(path, "path")?;
+    match cotar.0.get(path).map_err(error_status)? {
+        Some(bytes) => Ok(bytes),
+        None => Err(CotarStatus::NotFound),
+    }
+}
+
+/// Find the location of a file in the tar
+///
+/// # Safety
+/// `cotar` must be a open handle, `path` a nul terminated string and `out` valid for writes
+#[no_mangle]
+pub unsafe extern "C" fn cotar_info(
+    cotar: *const CotarHandle,
+    path: *const c_char,
+    out: *mut CotarEntry,
+) -> CotarStatus {
+    catch_panic(|| {
+        if out.is_null() {
+            return invalid_argument("out is null");
+        }
+        match lookup(cotar, path) {
+            Ok(Some(entry)) => {
+                *out = CotarEntry {
+                    hash: entry.hash,
+                    file_offset: entry.file_offset,
+                    file_size: entry.file_size,
+                };
+                CotarStatus::Ok
+            }
+            Ok(None) => CotarStatus::NotFound,
+            Err(status) => status,
+        }
+    })
+}
+
+/// Read a file into a caller owned buffer
+///
+/// The size of the file is written to `out_len`, if `buf_len` is too small nothing is copied and
+/// `BufferTooSmall` is returned, so `buf` can be null to query the size of a file
+///
+/// # Safety
+/// `cotar` must be a open handle, `path` a nul terminated string, `buf` valid for `buf_len` bytes
+/// of writes and `out_len` valid for writes
+#[no_mangle]
+pub unsafe extern "C" fn cotar_get(
+    cotar: *const CotarHandle,
+    path: *const c_char,
+    buf: *mut u8,
+    buf_len: usize,
+    out_len: *mut usize,
+) -> CotarStatus {
+    catch_panic(|| {
+        if out_len.is_null() {
+            return invalid_argument("out_len is null");
+        }
+        let bytes = match read_file(cotar, path) {
+            Ok(bytes) => bytes,
+            Err(status) => return status,
+        };
+        *out_len = bytes.len();
+        if buf_len < bytes.len() || buf.is_null() {
+            return CotarStatus::BufferTooSmall;
+        }
+        ptr::copy_nonoverlapping(bytes.as_ptr(), buf, bytes.len());
+        CotarStatus::Ok
+    })
+}
+
+/// Read a file into a buffer allocated by the library
+///
+/// The buffer written to `out` must be freed with `cotar_free` with the length from `out_len`
+///
+/// # Safety
+/// `cotar` must be a open handle, `path` a nul terminated string, `out` and `out_len` valid for writes
+#[no_mangle]
+pub unsafe extern "C" fn cotar_get_owned(
+    cotar: *const CotarHandle,
+    path: *const c_char,
+    out: *mut *mut u8,
+    out_len: *mut usize,
+) -> CotarStatus {
+    catch_panic(|| {
+        if out.is_null() || out_len.is_null() {
+            return invalid_argument("out is null");
+        }
+        *out = ptr::null_mut();
+        *out_len = 0;
+        match read_file(cotar, path) {
+            Ok(bytes) => {
+                // Files read from disk are uniquely owned so their buffer is handed over without a copy
+                let bytes = Vec::from(bytes).into_boxed_slice();
+                *out_len = bytes.len();
+                *out = Box::into_raw(bytes) as *mut u8;
+                CotarStatus::Ok
+            }
+            Err(status) => status,
+        }
+    })
+}
+
+/// Free a buffer from `cotar_get_owned`, null is ignored
+///
+/// # Safety
+/// `buf` must be null or a buffer from `cotar_get_owned` with its length `len`
+#[no_mangle]
+pub unsafe extern "C" fn cotar_free(buf: *mut u8, len: usize) {
+    catch_panic(|| {
+        if !buf.is_null() {
+            drop(Box::from_raw(ptr::slice_from_raw_parts_mut(buf, len)));
+        }
+        CotarStatus::Ok
+    });
+}
+
+/// Static description of a status
+#[no_mangle]
+pub extern "C" fn cotar_status_string(status: i32) -> *const c_char {
+    panic::catch_unwind(|| {
+        let message: &CStr = match status {
+            0 => c"ok",
+            1 => c"file not found",
+            2 => c"buffer too small",
+            -1 => c"invalid argument",
+            -2 => c"io error",
+            -3 => c"invalid archive",
+            -4 => c"panic",
+            _ => c"unknown status",
+        };
+        message.as_ptr()
+    })
+    .unwrap_or(ptr::null())
+}
+
+/// Message of the last error on the current thread, or null if there has been no error
+///
+/// The string is valid until the next error on the same thread
+#[no_mangle]
+pub extern "C" fn cotar_last_error() -> *const c_char {
+    panic::catch_unwind(|| {
+        LAST_ERROR.with(|last| {
+            last.borrow()
+                .as_ref()
+                .map_or(ptr::null(), |message| message.as_ptr())
+        })
+    })
+    .unwrap_or(ptr::null())
+}
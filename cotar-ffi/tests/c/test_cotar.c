@@ -0,0 +1,63 @@
+// Exercise the cotar C ABI against a archive created by tests/c_harness.rs
+//
+// Usage: test_cotar <archive.tar.co>
+#include <stdio.h>
+#include <string.h>
+
+#include "cotar.h"
+
+#define CHECK(cond)                                                   \
+  do {                                                                \
+    if (!(cond)) {                                                    \
+      const char *error = cotar_last_error();                         \
+      fprintf(stderr, "%s:%d check failed: %s (%s)\n", __FILE__,      \
+              __LINE__, #cond, error ? error : "no error");           \
+      return 1;                                                       \
+    }                                                                 \
+  } while (0)
+
+int main(int argc, char **argv) {
+  CHECK(argc == 2);
+
+  CotarHandle *cotar = NULL;
+  CHECK(cotar_open("missing.tar.co", &cotar) == COTAR_STATUS_IO);
+  CHECK(cotar == NULL);
+  CHECK(cotar_last_error() != NULL);
+  CHECK(cotar_open(NULL, &cotar) == COTAR_STATUS_INVALID_ARGUMENT);
+
+  CHECK(cotar_open(argv[1], &cotar) == COTAR_STATUS_OK);
+  CHECK(cotar != NULL);
+
+  const char *path = "tiles/0/0/0.pbf";
+  const char *expected = "tile 0/0/0";
+  size_t expected_len = strlen(expected);
+
+  CotarEntry entry;
+  CHECK(cotar_info(cotar, path, &entry) == COTAR_STATUS_OK);
+  CHECK(entry.file_size == expected_len);
+  CHECK(cotar_info(cotar, "tiles/9/9/9.pbf", &entry) == COTAR_STATUS_NOT_FOUND);
+
+  // Query the size, then read into a caller owned buffer
+  size_t len = 0;
+  CHECK(cotar_get(cotar, path, NULL, 0, &len) == COTAR_STATUS_BUFFER_TOO_SMALL);
+  CHECK(len == expected_len);
+  char buf[64];
+  CHECK(cotar_get(cotar, path, (uint8_t *)buf, sizeof(buf), &len) == COTAR_STATUS_OK);
+  CHECK(len == expected_len && memcmp(buf, expected, len) == 0);
+
+  // Read into a buffer owned by the library
+  uint8_t *owned = NULL;
+  CHECK(cotar_get_owned(cotar, path, &owned, &len) == COTAR_STATUS_OK);
+  CHECK(len == expected_len && memcmp(owned, expected, len) == 0);
+  cotar_free(owned, len);
+  CHECK(cotar_get_owned(cotar, "tiles/9/9/9.pbf", &owned, &len) == COTAR_STATUS_NOT_FOUND);
+  CHECK(owned == NULL);
+
+  CHECK(strcmp(cotar_status_string(COTAR_STATUS_NOT_FOUND), "file not found") == 0);
+  CHECK(strcmp(cotar_status_string(COTAR_STATUS_PANIC), "panic") == 0);
+
+  cotar_close(cotar);
+  cotar_close(NULL);
+  printf("ok\n");
+  return 0;
+}
@@ -0,0 +1,70 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use tar::{Builder, Header};
+
+/// Directory the cotar-ffi libraries are built into
+fn library_dir() -> PathBuf {
+    // Integration tests are built next to the libraries in target/<profile>/deps
+    let exe = std::env::current_exe().unwrap();
+    exe.parent().unwrap().to_path_buf()
+}
+
+/// Tar with a embedded index containing `tiles/0/0/0.pbf`
+fn create_archive(dir: &Path) -> PathBuf {
+    let tar = dir.join("archive.tar");
+    let mut builder = Builder::new(std::fs::File::create(&tar).unwrap());
+    for (path, data) in [
+        ("tiles/0/0/0.pbf", b"tile 0/0/0".as_slice()),
+        ("README.md", b"# Test archive"),
+    ] {
+        let mut header = Header::new_gnu();
+        header.set_size(data.len() as u64);
+        builder.append_data(&mut header, path, data).unwrap();
+    }
+    builder.into_inner().unwrap();
+
+    let mut index = cotar::CotarIndex::from_tar(tar.to_str().unwrap(), 0).unwrap();
+    let mut embedded = std::fs::read(&tar).unwrap();
+    embedded.extend_from_slice(&index.pack(1.25).unwrap().vec);
+    let archive = dir.join("archive.tar.co");
+    std::fs::write(&archive, embedded).unwrap();
+    archive
+}
+
+/// Compile the C test harness against the generated header and run it
+#[test]
+fn test_c_harness() {
+    let crate_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let temp_dir = tempfile::Builder::new()
+        .prefix("cotar-ffi-test-")
+        .tempdir()
+        .unwrap();
+    let dir = temp_dir.path();
+    let archive = create_archive(dir);
+
+    let lib_dir = library_dir();
+    let exe = dir.join("test_cotar");
+    let status = Command::new(std::env::var("CC").unwrap_or_else(|_| "cc".to_string()))
+        .arg(crate_dir.join("tests/c/test_cotar.c"))
+        .arg("-I")
+        .arg(crate_dir.join("include"))
+        .arg("-L")
+        .arg(&lib_dir)
+        .arg(format!("-Wl,-rpath,{}", lib_dir.display()))
+        .arg("-lcotar_ffi")
+        .arg("-o")
+        .arg(&exe)
+        .args(["-Wall", "-Werror"])
+        .status()
+        .expect("failed to run the C compiler");
+    assert!(status.success(), "failed to compile the C test harness");
+
+    let output = Command::new(&exe).arg(&archive).output().unwrap();
+    assert!(
+        output.status.success(),
+        "C test harness failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "ok\n");
+}
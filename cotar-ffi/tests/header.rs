@@ -0,0 +1,25 @@
+use std::path::Path;
+
+/// The committed header must match the header generated from the current source
+#[test]
+fn test_header_up_to_date() {
+    let crate_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let config = cbindgen::Config::from_file(crate_dir.join("cbindgen.toml")).unwrap();
+    let mut generated = Vec::new();
+    cbindgen::Builder::new()
+        .with_crate(crate_dir)
+        .with_config(config)
+        .generate()
+        .unwrap()
+        .write(&mut generated);
+
+    let header_path = crate_dir.join("include/cotar.h");
+    if std::env::var_os("COTAR_UPDATE_HEADER").is_some() {
+        std::fs::write(&header_path, &generated).unwrap();
+    }
+    let header = std::fs::read(&header_path).unwrap_or_default();
+    assert!(
+        header == generated,
+        "include/cotar.h is out of date, regenerate with COTAR_UPDATE_HEADER=1"
+    );
+}
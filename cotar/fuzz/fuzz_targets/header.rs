@@ -0,0 +1,10 @@
+#![no_main]
+
+use bytes::Bytes;
+use cotar::Cotar;
+use libfuzzer_sys::fuzz_target;
+
+// Parse the cotar header from arbitrary bytes
+fuzz_target!(|data: &[u8]| {
+    let _ = Cotar::header_from_bytes(Bytes::copy_from_slice(data));
+});
@@ -0,0 +1,30 @@
+#![no_main]
+
+use bytes::Bytes;
+use cotar::Cotar;
+use libfuzzer_sys::fuzz_target;
+
+// Open arbitrary bytes as a tar with a embedded index and as a separate index file
+fuzz_target!(|input: (&str, &[u8])| {
+    let (path, data) = input;
+    let data = Bytes::copy_from_slice(data);
+
+    for cotar in [
+        Cotar::from_source(data.clone()),
+        Cotar::from_sources(data.clone(), data),
+    ] {
+        let Ok(mut cotar) = cotar else {
+            continue;
+        };
+        let _ = cotar.get(path);
+        if cotar.load_index().is_ok() {
+            let _ = cotar.get(path);
+        }
+        if cotar.load_path_table().is_ok() {
+            let _ = cotar.get(path);
+            if let Some(table) = cotar.path_table() {
+                let _ = table.with_prefix(path).count();
+            }
+        }
+    }
+});
@@ -0,0 +1,33 @@
+#![no_main]
+
+use bytes::Bytes;
+use cotar::{Cotar, CotarIndex};
+use libfuzzer_sys::fuzz_target;
+
+// Look up a file that points at arbitrary bytes, parsing the tar header in front of it
+fuzz_target!(|input: (&str, u64, u64, bool, &[u8])| {
+    let (path, file_offset, file_size, v3, tar) = input;
+
+    let mut index = CotarIndex::new();
+    if v3 {
+        index.set_version(3);
+    }
+    if index.add(path, file_offset, file_size).is_err() {
+        return;
+    }
+    let Ok(index) = index.pack(1.25) else {
+        return;
+    };
+
+    let mut embedded = tar.to_vec();
+    embedded.extend_from_slice(&index.vec);
+    let Ok(mut cotar) = Cotar::from_source(Bytes::from(embedded)) else {
+        return;
+    };
+    cotar.set_verify_paths(true);
+    let _ = cotar.get(path);
+    let _ = cotar.get_many(&[path, path], 512);
+    if let Ok(Some(mut reader)) = cotar.reader(path) {
+        let _ = std::io::copy(&mut reader, &mut std::io::sink());
+    }
+});
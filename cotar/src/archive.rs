@@ -0,0 +1,445 @@
+use bytes::Bytes;
+use cotar_core::{
+    CotarHeader, CotarIndexEntry, HashAlgorithm, Index, Lookup, COTAR_V2_HEADER_SIZE,
+    COTAR_V3_HEADER_SIZE,
+};
+use tracing::trace;
+
+use crate::cotar::MAX_MERGED_READ_SIZE;
+use crate::encoding;
+use crate::error::{CotarError, CotarResult};
+use crate::path_table::{self, PathTable, COTAR_PATH_TABLE_HEADER_SIZE};
+use crate::source::Source;
+use crate::stat::CotarStat;
+use crate::tar_header::{TarHeader, MAX_EXTENSION_LOOKBACK, TAR_EXTENSION_LOOKBACK};
+
+/// End of a index with its offset from the start of the index
+pub(crate) type IndexTail = (u64, Bytes);
+
+/// State shared by `Cotar` and `AsyncCotar`
+///
+/// Everything that does not read from a source lives here, so the readers only do the reads
+#[derive(Debug, Clone)]
+pub(crate) struct ArchiveState {
+    /// Number of bytes at the start of the tar that files can be stored in
+    pub data_size: u64,
+    /// Entire index when it has been loaded into memory with `load_index`
+    pub index_bytes: Option<Bytes>,
+    /// End of the index read when opening, with its offset from the start of the index
+    pub index_tail: Option<IndexTail>,
+    /// Offset and size of the path table in front of a embedded index
+    pub path_table_range: Option<(u64, u64)>,
+    /// Has the path table footer in front of a embedded index been read
+    table_frame_read: bool,
+    /// Paths of the index slots when loaded with `load_path_table`
+    pub path_table: Option<PathTable>,
+    /// Check the tar header of a file matches the path being looked up
+    pub verify_paths: bool,
+    /// Largest file that `get` will read into memory
+    pub max_file_size: u64,
+    /// Decode compressed files in `get` and `get_many`
+    pub decode: bool,
+}
+
+/// Number of bytes to read from the end of a source of `size` bytes to open its embedded index
+///
+/// Reads at least enough of the footer for v3 index options
+pub(crate) fn tail_len(size: u64, tail_size: u64) -> CotarResult<u64> {
+    if size < COTAR_V2_HEADER_SIZE {
+        return Err(CotarError::TruncatedIndex);
+    }
+    Ok(size.min(tail_size.max(COTAR_V3_HEADER_SIZE)))
+}
+
+/// Number of bytes to read from the start of a separate index of `size` bytes to open it
+pub(crate) fn head_len(size: u64) -> CotarResult<u64> {
+    if size < COTAR_V2_HEADER_SIZE {
+        return Err(CotarError::TruncatedIndex);
+    }
+    Ok(size.min(COTAR_V3_HEADER_SIZE))
+}
+
+impl ArchiveState {
+    fn new(data_size: u64) -> Self {
+        ArchiveState {
+            data_size,
+            index_bytes: None,
+            index_tail: None,
+            path_table_range: None,
+            table_frame_read: false,
+            path_table: None,
+            verify_paths: false,
+            max_file_size: u64::MAX,
+            decode: false,
+        }
+    }
+
+    /// Open the index at the end of a source of `size` bytes from the `tail_len` bytes at its end
+    ///
+    /// Index slots in the tail are kept so lookups that land in them do not read the source.
+    /// The footer of a path table in front of the index is only parsed if the tail covers it,
+    /// otherwise it is read from `table_frame_offset` when the path table is loaded
+    pub fn embedded(size: u64, tail: Bytes, tail_size: u64) -> CotarResult<(Index, Self)> {
+        let index = Index::from_tail(&tail, size)?;
+        let tail_offset = size - tail.len() as u64;
+        let mut state = ArchiveState::new(index.data_size);
+        if let Some(frame_offset) = state.table_frame_offset(&index) {
+            if let Some(start) = frame_offset.checked_sub(tail_offset) {
+                let end = start + COTAR_PATH_TABLE_HEADER_SIZE;
+                state.set_table_frame(frame_offset, tail.slice(start as usize..end as usize))?;
+            }
+        }
+        (state.index_bytes, state.index_tail) = split_index_tail(
+            &index.header,
+            index.index_offset,
+            tail_offset,
+            tail,
+            tail_size,
+        )?;
+        Ok((index, state))
+    }
+
+    /// Offset of the path table footer in front of a embedded index if it has not been read yet
+    pub fn table_frame_offset(&self, index: &Index) -> Option<u64> {
+        match self.table_frame_read {
+            true => None,
+            false => path_table::frame_offset(index.index_offset, 0, 0, false),
+        }
+    }
+
+    /// Find the path table in front of a embedded index from the footer read at `frame_offset`
+    ///
+    /// Files have to end before the path table
+    pub fn set_table_frame(&mut self, frame_offset: u64, frame: Bytes) -> CotarResult<()> {
+        self.table_frame_read = true;
+        self.path_table_range =
+            path_table::table_range(frame, frame_offset, self.data_size, false)?;
+        if let Some((offset, _)) = self.path_table_range {
+            self.data_size = offset;
+        }
+        Ok(())
+    }
+
+    /// Open a index of `size` bytes stored separately from a tar of `data_size` bytes from the
+    /// `head_len` bytes at its start
+    pub fn separate(head: &[u8], size: u64, data_size: u64) -> CotarResult<(Index, Self)> {
+        let index = Index::from_head(head, size, data_size)?;
+        let mut state = ArchiveState::new(data_size);
+        // Path tables are stored after a separate index so they do not overlap the tar
+        state.table_frame_read = true;
+        Ok((index, state))
+    }
+
+    /// Keep a index read with `load_index` once its header and footer are checked
+    pub fn set_index_bytes(&mut self, index: &Index, bytes: Bytes) -> CotarResult<()> {
+        index.header.check_index(&bytes)?;
+        self.index_bytes = Some(bytes);
+        Ok(())
+    }
+
+    /// Slot at a offset relative to the start of the index if it is in memory
+    ///
+    /// Returns None if the slot needs to be read from the source
+    pub fn cached_slot(&self, index: &Index, offset: u64) -> CotarResult<Option<Bytes>> {
+        let len = cotar_core::index_entry_size(index.header.version);
+        match (&self.index_bytes, &self.index_tail) {
+            (Some(bytes), _) => Ok(Some(bytes.read_exact(offset, len)?)),
+            (None, Some((tail_offset, tail))) if offset >= *tail_offset => {
+                Ok(Some(tail.read_exact(offset - tail_offset, len)?))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Start looking up a path, see `PathLookup`
+    pub fn lookup<'a>(&'a self, index: &Index, path: &'a str) -> PathLookup<'a> {
+        PathLookup {
+            lookup: index.lookup(index.hash_algorithm().hash(path.as_bytes())),
+            hash_algorithm: index.hash_algorithm(),
+            path,
+            state: self,
+            collision: false,
+        }
+    }
+
+    /// Ensure a file can be read into memory
+    pub fn check_file_size(&self, entry: &CotarIndexEntry) -> CotarResult<()> {
+        match entry.file_size > self.max_file_size {
+            true => Err(CotarError::FileTooLarge {
+                file_size: entry.file_size,
+                max_file_size: self.max_file_size,
+            }),
+            false => Ok(()),
+        }
+    }
+
+    /// Largest read that `get_many` merges files into
+    pub fn max_merged_read(&self) -> u64 {
+        self.max_file_size.min(MAX_MERGED_READ_SIZE)
+    }
+
+    /// Decode a file read by `get` if decoding is enabled
+    pub fn decode_file(&self, path: &str, bytes: Bytes) -> CotarResult<Bytes> {
+        match self.decode {
+            true => encoding::decode_file(path, bytes, self.max_file_size),
+            false => Ok(bytes),
+        }
+    }
+
+    /// Decode the files read by `get_many` if decoding is enabled
+    pub fn decode_files(&self, paths: &[&str], files: &mut [Option<Bytes>]) -> CotarResult<()> {
+        if !self.decode {
+            return Ok(());
+        }
+        for (path, file) in paths.iter().zip(files.iter_mut()) {
+            if let Some(bytes) = file.take() {
+                *file = Some(self.decode_file(path, bytes)?);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Lookup of a file path that checks each entry with the hash of the path against the path table,
+/// or against the tar header in front of the file when path verification is enabled
+///
+/// The reader reads the slot at `offset()` and feeds it to `step()` until `offset()` returns None,
+/// entries returned from `step()` need their tar header checked and passed to `verify()`
+pub(crate) struct PathLookup<'a> {
+    lookup: Lookup,
+    hash_algorithm: HashAlgorithm,
+    path: &'a str,
+    state: &'a ArchiveState,
+    /// Has a entry with the hash been found for another path
+    collision: bool,
+}
+
+impl PathLookup<'_> {
+    /// Offset from the start of the index of the next slot to read, None once the lookup is complete
+    pub fn offset(&self) -> Option<u64> {
+        self.lookup.offset()
+    }
+
+    /// Check the bytes of the slot at `offset()`
+    ///
+    /// Returns the entry if its tar header needs to be checked against the path
+    pub fn step(&mut self, bytes: &[u8]) -> CotarResult<Option<CotarIndexEntry>> {
+        let Some(entry) = self.lookup.step(bytes)? else {
+            return Ok(None);
+        };
+        match &self.state.path_table {
+            // The path table lists every path so a mismatch is not a collision
+            Some(table) => {
+                if table.path(self.lookup.result().slot) != Some(self.path) {
+                    self.lookup.reject();
+                }
+                Ok(None)
+            }
+            None if self.state.verify_paths => Ok(Some(entry)),
+            None => Ok(None),
+        }
+    }
+
+    /// Hash of the file named by a tar header that does not match the path
+    ///
+    /// Hard links are indexed with the entry of the file they link to, so the tar header in front
+    /// of a link names its target. Returns None if the header path has the same hash as the path,
+    /// as then the entry belongs to another file with a colliding hash
+    pub fn link_target(&self, header_path: &[u8]) -> Option<u64> {
+        let hash = self.hash_algorithm.hash(header_path);
+        (hash != self.lookup.result().hash).then_some(hash)
+    }
+
+    /// Record if the tar header of the entry returned by `step()` matches the path
+    pub fn verify(&mut self, matched: bool) {
+        if !matched {
+            // Another file has the same hash, keep looking in case there are duplicate hashes
+            self.collision = true;
+            self.lookup.reject();
+        }
+    }
+
+    /// Entry for the path, a `HashCollision` error if the hash was only found for other paths
+    pub fn finish(self) -> CotarResult<Option<CotarIndexEntry>> {
+        let path = self.path;
+        let probes = self.lookup.probes();
+        if let Some(entry) = self.lookup.result().entry {
+            trace!(path, probes, "Index probe found");
+            return Ok(Some(entry));
+        }
+        trace!(
+            path,
+            probes,
+            collision = self.collision,
+            "Index probe missed"
+        );
+        match self.collision {
+            true => Err(CotarError::HashCollision(path.to_string())),
+            false => Ok(None),
+        }
+    }
+}
+
+/// Do two index entries point at the same file
+pub(crate) fn same_file(entry: &CotarIndexEntry, other: &CotarIndexEntry) -> bool {
+    entry.file_offset == other.file_offset && entry.file_size == other.file_size
+}
+
+/// Lookback for the next read of the tar header of a entry, after the header read with `lookback`
+/// from `offset` did not name `path`
+///
+/// Most paths fit inside the tar header so the first read has no lookback, then extension
+/// headers are searched for in the `TAR_EXTENSION_LOOKBACK` bytes in front of it. Larger
+/// extension headers are searched for by doubling the lookback while the header could hold a
+/// truncated `path`, up to `MAX_EXTENSION_LOOKBACK` after which a `UnverifiedPath` error is
+/// returned.
+///
+/// Returns None once the header is known to name another file
+pub(crate) fn next_lookback(
+    lookback: u64,
+    offset: u64,
+    header: &TarHeader,
+    path: &str,
+) -> CotarResult<Option<u64>> {
+    if lookback == 0 {
+        return Ok(Some(TAR_EXTENSION_LOOKBACK));
+    }
+    // The header is complete if its extensions were found, the read started at the start of the
+    // tar or the path would fit in the header
+    if header.has_extensions() || offset == 0 || path.len() <= header.path_bytes().len() {
+        return Ok(None);
+    }
+    match lookback < MAX_EXTENSION_LOOKBACK {
+        true => Ok(Some(lookback * 2)),
+        false => Err(CotarError::UnverifiedPath(path.to_string())),
+    }
+}
+
+/// Parse the tar header read from `header_range` in front of a entry
+pub(crate) fn parse_header(entry: &CotarIndexEntry, bytes: &[u8]) -> CotarResult<TarHeader> {
+    TarHeader::parse(bytes).ok_or(CotarError::InvalidTarHeader {
+        file_offset: entry.file_offset,
+    })
+}
+
+/// Metadata of a entry from the tar header read from `header_range` in front of the file
+pub(crate) fn stat_entry(entry: &CotarIndexEntry, bytes: &[u8]) -> CotarResult<CotarStat> {
+    Ok(CotarStat::from_header(&parse_header(entry, bytes)?, entry))
+}
+
+/// Path of a entry from the tar header in front of the file, None if it cannot be parsed or the
+/// path is not UTF-8
+pub(crate) fn header_path(bytes: &[u8]) -> Option<String> {
+    TarHeader::parse(bytes)
+        .and_then(|header| String::from_utf8(header.path_bytes().into_owned()).ok())
+}
+
+/// Split the tail read when opening a embedded index into the entire index if the tail covers it,
+/// or the part of the index that is in the tail
+///
+/// `tail_offset` is the offset of the tail in the source, tails smaller than the footer are not kept
+fn split_index_tail(
+    header: &CotarHeader,
+    index_offset: u64,
+    tail_offset: u64,
+    tail: Bytes,
+    tail_size: u64,
+) -> CotarResult<(Option<Bytes>, Option<IndexTail>)> {
+    if tail_size <= cotar_core::header_size(header.version) {
+        return Ok((None, None));
+    }
+    match index_offset.checked_sub(tail_offset) {
+        Some(start) => {
+            let index = tail.slice(start as usize..);
+            header.check_index(&index)?;
+            Ok((Some(index), None))
+        }
+        None => Ok((None, Some((tail_offset - index_offset, tail)))),
+    }
+}
+
+/// Byte range of the tar that covers one or more files
+pub(crate) struct ReadRange {
+    pub offset: u64,
+    pub len: u64,
+    /// Index of the file in the request with its offset from the start of the range and size
+    files: Vec<(usize, u64, u64)>,
+}
+
+impl ReadRange {
+    /// Slice the bytes of the range into the files it covers
+    pub fn split(&self, bytes: Bytes, output: &mut [Option<Bytes>]) {
+        for (index, offset, size) in &self.files {
+            output[*index] = Some(bytes.slice(*offset as usize..(offset + size) as usize));
+        }
+    }
+}
+
+/// Merge the reads of files that are within `max_gap` bytes of each other into larger reads
+///
+/// Reads are only merged while they stay within `max_len` bytes, larger files are read by themselves
+pub(crate) fn coalesce_reads(
+    entries: &[Option<CotarIndexEntry>],
+    max_gap: u64,
+    max_len: u64,
+) -> Vec<ReadRange> {
+    let mut found: Vec<(usize, &CotarIndexEntry)> = entries
+        .iter()
+        .enumerate()
+        .filter_map(|(index, entry)| entry.as_ref().map(|entry| (index, entry)))
+        .collect();
+    found.sort_by_key(|(_, entry)| entry.file_offset);
+
+    let mut ranges: Vec<ReadRange> = Vec::new();
+    for (index, entry) in found {
+        let start = entry.file_offset;
+        let end = start + entry.file_size;
+        match ranges.last_mut() {
+            Some(range)
+                if start <= (range.offset + range.len).saturating_add(max_gap)
+                    && end - range.offset <= max_len =>
+            {
+                range.len = range.len.max(end - range.offset);
+                range
+                    .files
+                    .push((index, start - range.offset, entry.file_size));
+            }
+            _ => ranges.push(ReadRange {
+                offset: start,
+                len: end - start,
+                files: vec![(index, 0, entry.file_size)],
+            }),
+        }
+    }
+    ranges
+}
+
+#[test]
+fn test_coalesce_reads_limit() {
+    let entries: Vec<_> = (0..4)
+        .map(|i| {
+            Some(CotarIndexEntry {
+                hash: i + 1,
+                file_offset: i * 1024 + 512,
+                file_size: 100,
+            })
+        })
+        .collect();
+
+    let ranges = coalesce_reads(&entries, 1024, u64::MAX);
+    assert_eq!(ranges.len(), 1);
+    assert_eq!((ranges[0].offset, ranges[0].len), (512, 3 * 1024 + 100));
+
+    // Merged reads are split before they grow past the limit
+    let ranges = coalesce_reads(&entries, 1024, 2048);
+    let ranges: Vec<_> = ranges
+        .iter()
+        .map(|range| (range.offset, range.len))
+        .collect();
+    assert_eq!(ranges, [(512, 1124), (2560, 1124)]);
+
+    // Any gap is merged over without overflowing
+    let ranges = coalesce_reads(&entries, u64::MAX, u64::MAX);
+    assert_eq!(ranges.len(), 1);
+    assert_eq!((ranges[0].offset, ranges[0].len), (512, 3 * 1024 + 100));
+}
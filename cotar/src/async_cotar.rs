@@ -0,0 +1,669 @@
+use std::future::Future;
+use std::io::Result as IoResult;
+use std::sync::Arc;
+
+use bytes::Bytes;
+use tracing::{debug, instrument, trace};
+
+use cotar_core::{
+    index_entry_size, CotarHeader, CotarIndexEntry, HashAlgorithm, Index, ProbeResult,
+};
+
+use crate::archive::{self, coalesce_reads, ArchiveState, PathLookup};
+use crate::dataview::DataView;
+use crate::encoding::ContentEncoding;
+use crate::error::CotarResult;
+use crate::path_table::{self, PathTable, COTAR_PATH_TABLE_HEADER_SIZE};
+use crate::source::Source;
+use crate::stat::CotarStat;
+use crate::tar_header::{header_range, TAR_EXTENSION_LOOKBACK};
+
+/// Random access source of bytes that an `AsyncCotar` can be read from
+pub trait AsyncSource {
+    /// Total size of the source in bytes
+    fn size(&self) -> u64;
+
+    /// Read exactly `len` bytes starting at `offset`
+    fn read_exact(&self, offset: u64, len: u64) -> impl Future<Output = IoResult<Bytes>> + Send;
+}
+
+/// Read only view of a file on disk for use with tokio
+///
+/// Reads are run on tokio's blocking thread pool using positional reads
+#[derive(Debug, Clone)]
+pub struct AsyncDataView {
+    view: DataView,
+    pub size: u64,
+}
+
+impl AsyncDataView {
+    pub async fn open(file_name: &str) -> IoResult<Self> {
+        let file_name = file_name.to_string();
+        let view = tokio::task::spawn_blocking(move || DataView::open(&file_name))
+            .await
+            .map_err(std::io::Error::other)??;
+
+        Ok(AsyncDataView {
+            size: view.size,
+            view,
+        })
+    }
+}
+
+impl AsyncSource for AsyncDataView {
+    fn size(&self) -> u64 {
+        self.size
+    }
+
+    async fn read_exact(&self, offset: u64, len: u64) -> IoResult<Bytes> {
+        let view = self.view.clone();
+        tokio::task::spawn_blocking(move || view.read_exact(offset, len))
+            .await
+            .map_err(std::io::Error::other)?
+    }
+}
+
+/// In memory buffer
+impl AsyncSource for Bytes {
+    fn size(&self) -> u64 {
+        Source::size(self)
+    }
+
+    async fn read_exact(&self, offset: u64, len: u64) -> IoResult<Bytes> {
+        Source::read_exact(self, offset, len)
+    }
+}
+
+impl<S: AsyncSource + Send + Sync + ?Sized> AsyncSource for Arc<S> {
+    fn size(&self) -> u64 {
+        (**self).size()
+    }
+
+    fn read_exact(&self, offset: u64, len: u64) -> impl Future<Output = IoResult<Bytes>> + Send {
+        (**self).read_exact(offset, len)
+    }
+}
+
+/// Async cotar archive reader
+///
+/// Mirrors `Cotar` for sources that are read asynchronously
+#[derive(Debug, Clone)]
+pub struct AsyncCotar<S = AsyncDataView> {
+    /// Cotar index version generally v2
+    pub version: u8,
+    /// Algorithm used to hash file paths
+    pub hash_algorithm: HashAlgorithm,
+    /// Number of entries in the tar archive
+    pub entries: u64,
+    /// Offset in the view for the index
+    pub index_offset: u64,
+    /// View of the tar archive
+    pub view: S,
+    /// Index reference if the index is a separate file
+    pub view_index: Option<S>,
+    /// Index state shared with `Cotar`
+    state: ArchiveState,
+}
+
+impl AsyncCotar {
+    /// Load a cotar from a packed tar file
+    ///
+    /// The index of the tar must be the final bytes of the tar file
+    #[instrument(level = "debug")]
+    pub async fn from_tar(file_name: &str) -> CotarResult<Self> {
+        AsyncCotar::from_source(AsyncDataView::open(file_name).await?).await
+    }
+
+    /// Load a cotar from a tar and index file
+    #[instrument(level = "debug")]
+    pub async fn from_tar_index(tar_file_name: &str, index_file_name: &str) -> CotarResult<Self> {
+        AsyncCotar::from_sources(
+            AsyncDataView::open(tar_file_name).await?,
+            AsyncDataView::open(index_file_name).await?,
+        )
+        .await
+    }
+}
+
+impl<S: AsyncSource> AsyncCotar<S> {
+    /// Load a cotar from a source containing a packed tar
+    ///
+    /// The index of the tar must be the final bytes of the source
+    pub async fn from_source(view: S) -> CotarResult<Self> {
+        AsyncCotar::from_source_with_tail(view, 0).await
+    }
+
+    /// Load a cotar from a source containing a packed tar, reading the last `tail_size` bytes
+    /// of the source with a single read
+    ///
+    /// See `Cotar::from_source_with_tail`
+    #[instrument(level = "debug", skip(view))]
+    pub async fn from_source_with_tail(view: S, tail_size: u64) -> CotarResult<Self> {
+        let size = view.size();
+        let len = archive::tail_len(size, tail_size)?;
+        let tail = view.read_exact(size - len, len).await?;
+        let (index, state) = ArchiveState::embedded(size, tail, tail_size)?;
+        debug!(
+            version = index.header.version,
+            entries = index.header.entries,
+            index_offset = index.index_offset,
+            index_loaded = state.index_bytes.is_some(),
+            "Cotar opened"
+        );
+        Ok(AsyncCotar::new(index, state, view, None))
+    }
+
+    /// Load a cotar from a source containing a tar and a source containing its index
+    #[instrument(level = "debug", skip_all)]
+    pub async fn from_sources(view: S, view_index: S) -> CotarResult<Self> {
+        let size = view_index.size();
+        let head = view_index.read_exact(0, archive::head_len(size)?).await?;
+        let (index, state) = ArchiveState::separate(&head, size, view.size())?;
+        debug!(
+            version = index.header.version,
+            entries = index.header.entries,
+            "Cotar opened with separate index"
+        );
+        Ok(AsyncCotar::new(index, state, view, Some(view_index)))
+    }
+
+    fn new(index: Index, state: ArchiveState, view: S, view_index: Option<S>) -> Self {
+        AsyncCotar {
+            version: index.header.version,
+            hash_algorithm: index.header.hash_algorithm,
+            entries: index.entries(),
+            index_offset: index.index_offset,
+            view,
+            view_index,
+            state,
+        }
+    }
+
+    /// Size of the index in bytes including the header and footer
+    pub fn index_size(&self) -> u64 {
+        self.index().header.index_size()
+    }
+
+    /// Read the entire index into memory
+    ///
+    /// By default the index is read lazily one slot at a time, once loaded lookups
+    /// only read the file contents from the source
+    ///
+    /// The header and footer of the loaded index are checked to match
+    pub async fn load_index(&mut self) -> CotarResult<()> {
+        let view_index = self.view_index.as_ref().unwrap_or(&self.view);
+        let bytes = view_index
+            .read_exact(self.index_offset, self.index_size())
+            .await?;
+        let index = self.index();
+        self.state.set_index_bytes(&index, bytes)
+    }
+
+    /// Has the index been loaded into memory with `load_index`
+    pub fn is_index_loaded(&self) -> bool {
+        self.state.index_bytes.is_some()
+    }
+
+    /// Read the path table stored next to the index
+    ///
+    /// Returns None if the archive does not have a path table
+    pub async fn read_path_table(&self) -> CotarResult<Option<PathTable>> {
+        let range = match &self.view_index {
+            None => match self.state.table_frame_offset(&self.index()) {
+                // The table in front of a embedded index is found when opening if the tail
+                // covers its footer
+                None => self.state.path_table_range,
+                Some(offset) => {
+                    let frame = self
+                        .view
+                        .read_exact(offset, COTAR_PATH_TABLE_HEADER_SIZE)
+                        .await?;
+                    path_table::table_range(frame, offset, self.state.data_size, false)?
+                }
+            },
+            Some(view_index) => {
+                let size = view_index.size();
+                match path_table::frame_offset(self.index_offset, self.index_size(), size, true) {
+                    Some(offset) => {
+                        let frame = view_index
+                            .read_exact(offset, COTAR_PATH_TABLE_HEADER_SIZE)
+                            .await?;
+                        path_table::table_range(frame, offset, size, true)?
+                    }
+                    None => None,
+                }
+            }
+        };
+        let Some((offset, len)) = range else {
+            return Ok(None);
+        };
+        let view_index = self.view_index.as_ref().unwrap_or(&self.view);
+        let bytes = view_index.read_exact(offset, len).await?;
+        Ok(Some(PathTable::from_bytes(bytes, self.entries)?))
+    }
+
+    /// Read the path table into memory
+    ///
+    /// See `Cotar::load_path_table`
+    pub async fn load_path_table(&mut self) -> CotarResult<bool> {
+        if let Some(offset) = self.state.table_frame_offset(&self.index()) {
+            let frame = self
+                .view
+                .read_exact(offset, COTAR_PATH_TABLE_HEADER_SIZE)
+                .await?;
+            self.state.set_table_frame(offset, frame)?;
+        }
+        self.state.path_table = self.read_path_table().await?;
+        Ok(self.state.path_table.is_some())
+    }
+
+    /// Path table loaded with `load_path_table`
+    pub fn path_table(&self) -> Option<&PathTable> {
+        self.state.path_table.as_ref()
+    }
+
+    /// Location of the index for lookups with `cotar_core`
+    fn index(&self) -> Index {
+        Index {
+            header: CotarHeader::new(self.version, self.entries as u32, self.hash_algorithm),
+            index_offset: self.index_offset,
+            data_size: self.state.data_size,
+        }
+    }
+
+    /// Limit the size of files that `get` and `get_many` will read into memory
+    ///
+    /// See `Cotar::set_max_file_size`
+    pub fn set_max_file_size(&mut self, max_file_size: u64) {
+        self.state.max_file_size = max_file_size;
+    }
+
+    /// Decode gzip, zstd and brotli files returned from `get` and `get_many`
+    ///
+    /// Encodings are detected with `ContentEncoding::detect`, decoding an encoding needs its
+    /// `gzip`, `zstd` or `brotli` feature otherwise a `UnsupportedEncoding` error is returned.
+    /// Decoded files are also limited by `set_max_file_size`.
+    ///
+    /// Use `get_encoded` to read the raw bytes and pass the encoding through to HTTP clients
+    pub fn set_decode(&mut self, decode: bool) {
+        self.state.decode = decode;
+    }
+
+    /// Read a index slot at a offset relative to the start of the index
+    async fn read_slot(&self, offset: u64) -> CotarResult<Bytes> {
+        let index = self.index();
+        match self.state.cached_slot(&index, offset)? {
+            Some(bytes) => Ok(bytes),
+            None => {
+                let view_index = self.view_index.as_ref().unwrap_or(&self.view);
+                let len = index_entry_size(self.version);
+                Ok(view_index
+                    .read_exact(self.index_offset + offset, len)
+                    .await?)
+            }
+        }
+    }
+
+    /// Read the entry stored in a slot of the index
+    ///
+    /// Returns None if the slot is empty, entries that point outside of the tar return a
+    /// `OutOfBounds` error.
+    ///
+    /// Every entry can be listed by reading the slots from 0 to `entries`
+    pub async fn entry_at(&self, slot: u64) -> CotarResult<Option<CotarIndexEntry>> {
+        let index = self.index();
+        let Some(offset) = index.slot_offset(slot) else {
+            return Ok(None);
+        };
+        Ok(index.entry(&self.read_slot(offset).await?)?)
+    }
+
+    /// Recover the path of a entry from the tar header in front of the file
+    ///
+    /// Returns None if the header cannot be parsed or the path is not UTF-8.
+    /// Files stored as hard links return the path of the file they link to
+    pub async fn read_path(&self, entry: &CotarIndexEntry) -> CotarResult<Option<String>> {
+        let (offset, len) = match header_range(entry.file_offset, TAR_EXTENSION_LOOKBACK) {
+            Ok(range) => range,
+            Err(_) => return Ok(None),
+        };
+        Ok(archive::header_path(
+            &self.view.read_exact(offset, len).await?,
+        ))
+    }
+
+    /// Read the metadata of a file from the tar header in front of it
+    ///
+    /// Returns None if the file is not found
+    pub async fn stat(&self, path: &str) -> CotarResult<Option<CotarStat>> {
+        match self.info(path).await? {
+            None => Ok(None),
+            Some(entry) => Ok(Some(self.stat_entry(&entry).await?)),
+        }
+    }
+
+    /// Read the metadata of a entry from the tar header in front of the file
+    pub async fn stat_entry(&self, entry: &CotarIndexEntry) -> CotarResult<CotarStat> {
+        let (offset, len) = header_range(entry.file_offset, TAR_EXTENSION_LOOKBACK)?;
+        archive::stat_entry(entry, &self.view.read_exact(offset, len).await?)
+    }
+
+    /// Read a file from the tar archive
+    ///
+    /// Compressed files are decoded if enabled with `set_decode`
+    ///
+    /// Returns None if the file is not found
+    pub async fn get(&self, path: &str) -> CotarResult<Option<Bytes>> {
+        match self.read_file(path).await? {
+            None => Ok(None),
+            Some(bytes) => Ok(Some(self.state.decode_file(path, bytes)?)),
+        }
+    }
+
+    /// Read the raw bytes of a file with the encoding detected from its path and magic bytes
+    ///
+    /// Files are never decoded, so the bytes can be sent with a HTTP `Content-Encoding` header
+    ///
+    /// Returns None if the file is not found
+    pub async fn get_encoded(
+        &self,
+        path: &str,
+    ) -> CotarResult<Option<(Bytes, Option<ContentEncoding>)>> {
+        Ok(self.read_file(path).await?.map(|bytes| {
+            let encoding = ContentEncoding::detect(path, &bytes);
+            (bytes, encoding)
+        }))
+    }
+
+    /// Read the raw bytes of a file from the tar archive
+    async fn read_file(&self, path: &str) -> CotarResult<Option<Bytes>> {
+        match self.info(path).await? {
+            None => Ok(None),
+            Some(entry) => {
+                self.state.check_file_size(&entry)?;
+                trace!(
+                    path,
+                    file_offset = entry.file_offset,
+                    file_size = entry.file_size,
+                    "Read file"
+                );
+                let bytes = self
+                    .view
+                    .read_exact(entry.file_offset, entry.file_size)
+                    .await?;
+                Ok(Some(bytes))
+            }
+        }
+    }
+
+    /// Read the raw bytes of many files from the tar archive
+    ///
+    /// All index entries are resolved first, then files that are within `max_gap` bytes of each other
+    /// in the tar are read together with a single read. Merged reads are limited to
+    /// `MAX_MERGED_READ_SIZE` bytes or the size set with `set_max_file_size` if it is smaller.
+    ///
+    /// Compressed files are decoded if enabled with `set_decode`
+    ///
+    /// Returns the files in the same order as `paths`, None for files that are not found
+    pub async fn get_many(&self, paths: &[&str], max_gap: u64) -> CotarResult<Vec<Option<Bytes>>> {
+        let mut entries = Vec::with_capacity(paths.len());
+        for path in paths {
+            let entry = self.info(path).await?;
+            if let Some(entry) = &entry {
+                self.state.check_file_size(entry)?;
+            }
+            entries.push(entry);
+        }
+
+        let mut output = vec![None; paths.len()];
+        for range in coalesce_reads(&entries, max_gap, self.state.max_merged_read()) {
+            trace!(offset = range.offset, len = range.len, "Read file range");
+            let bytes = self.view.read_exact(range.offset, range.len).await?;
+            range.split(bytes, &mut output);
+        }
+        self.state.decode_files(paths, &mut output)?;
+        Ok(output)
+    }
+
+    /// Read the metadata entry for a file path
+    ///
+    /// Returns None if file is not found
+    ///
+    /// When path verification is enabled the tar header of the file is checked against `path`,
+    /// if no entry matches but the hash was found an error is returned
+    pub async fn info(&self, path: &str) -> CotarResult<Option<CotarIndexEntry>> {
+        let index = self.index();
+        let mut lookup = self.state.lookup(&index, path);
+        while let Some(offset) = lookup.offset() {
+            if let Some(entry) = lookup.step(&self.read_slot(offset).await?)? {
+                let matched = self.verify_path(&lookup, path, &entry).await?;
+                lookup.verify(matched);
+            }
+        }
+        lookup.finish()
+    }
+
+    /// Read the metadata entry for a path hash, hashed with the archive's `hash_algorithm`
+    ///
+    /// Paths are not verified, so with duplicate hashes the first entry in the index is returned
+    ///
+    /// Returns None if the hash is not found
+    pub async fn info_hash(&self, hash: u64) -> CotarResult<Option<CotarIndexEntry>> {
+        Ok(self.probe(hash).await?.entry)
+    }
+
+    /// Probe the index for a path hash, reporting the slot the entry is in and how far it is from
+    /// the slot the hash starts probing at
+    ///
+    /// Indexes without any slots report slot 0
+    pub async fn probe(&self, hash: u64) -> CotarResult<ProbeResult> {
+        let mut lookup = self.index().lookup(hash);
+        while let Some(offset) = lookup.offset() {
+            lookup.step(&self.read_slot(offset).await?)?;
+        }
+        let result = lookup.result();
+        trace!(
+            hash,
+            slot = result.slot,
+            distance = result.distance,
+            "Hash probe"
+        );
+        Ok(result)
+    }
+
+    /// Verify paths on lookup by reading the tar header in front of each file found
+    ///
+    /// See `Cotar::set_verify_paths`
+    pub fn set_verify_paths(&mut self, verify: bool) {
+        self.state.verify_paths = verify;
+    }
+
+    /// Does the tar header of the entry match the path, or is the path a hard link to the file
+    /// named by the tar header
+    async fn verify_path(
+        &self,
+        lookup: &PathLookup<'_>,
+        path: &str,
+        entry: &CotarIndexEntry,
+    ) -> CotarResult<bool> {
+        let mut lookback = 0;
+        loop {
+            let (offset, len) = header_range(entry.file_offset, lookback)?;
+            let header = archive::parse_header(entry, &self.view.read_exact(offset, len).await?)?;
+            if header.path_bytes().as_ref() == path.as_bytes() {
+                return Ok(true);
+            }
+            // Hard links are checked once extension headers have been searched for
+            if lookback == TAR_EXTENSION_LOOKBACK {
+                if let Some(target) = lookup.link_target(&header.path_bytes()) {
+                    let target = self.probe(target).await?.entry;
+                    if target.is_some_and(|target| archive::same_file(entry, &target)) {
+                        return Ok(true);
+                    }
+                }
+            }
+            match archive::next_lookback(lookback, offset, &header, path)? {
+                Some(next) => lookback = next,
+                None => return Ok(false),
+            }
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_async_from_tar_get() {
+    use crate::test_util::{create_archive, TEST_FILES};
+
+    let archive = create_archive("async_from_tar_get", &TEST_FILES);
+
+    for cotar in [
+        AsyncCotar::from_tar(archive.embedded()).await.unwrap(),
+        AsyncCotar::from_tar_index(archive.tar(), archive.index())
+            .await
+            .unwrap(),
+    ] {
+        crate::test_util::assert_test_files_async(&cotar).await;
+    }
+}
+
+#[tokio::test]
+async fn test_async_load_index() {
+    use crate::test_util::{create_archive, TEST_FILES};
+
+    let archive = create_archive("async_load_index", &TEST_FILES);
+    let mut cotar = AsyncCotar::from_tar(archive.embedded()).await.unwrap();
+    cotar.load_index().await.unwrap();
+    assert!(cotar.is_index_loaded());
+    cotar.set_verify_paths(true);
+
+    crate::test_util::assert_test_files_async(&cotar).await;
+}
+
+#[tokio::test]
+async fn test_async_verify_paths_hard_links() {
+    use crate::test_util::{create_archive_with_links, TEST_FILES};
+
+    let links = [("tiles/2/0/0.pbf", "tiles/0/0/0.pbf")];
+    let archive = create_archive_with_links("async_verify_paths_hard_links", &TEST_FILES, &links);
+    let mut cotar = AsyncCotar::from_tar(archive.embedded()).await.unwrap();
+    cotar.set_verify_paths(true);
+    let file = cotar.get("tiles/2/0/0.pbf").await.unwrap().unwrap();
+    assert_eq!(file.as_ref(), b"tile 0/0/0");
+}
+
+#[tokio::test]
+async fn test_async_from_source_with_tail() {
+    use crate::test_util::{create_archive, TEST_FILES};
+    use crate::DEFAULT_TAIL_SIZE;
+
+    let archive = create_archive("async_from_source_with_tail", &TEST_FILES);
+    let embedded = Bytes::from(std::fs::read(archive.embedded()).unwrap());
+    for (tail_size, loaded) in [(DEFAULT_TAIL_SIZE, true), (40, false)] {
+        let cotar = AsyncCotar::from_source_with_tail(embedded.clone(), tail_size)
+            .await
+            .unwrap();
+        assert_eq!(cotar.is_index_loaded(), loaded);
+        crate::test_util::assert_test_files_async(&cotar).await;
+    }
+}
+
+#[tokio::test]
+async fn test_async_probe_hash() {
+    use crate::test_util::{create_archive, TEST_FILES};
+
+    let archive = create_archive("async_probe_hash", &TEST_FILES);
+    let cotar = AsyncCotar::from_tar(archive.embedded()).await.unwrap();
+    for (path, _) in TEST_FILES {
+        let hash = cotar.hash_algorithm.hash(path.as_bytes());
+        let entry = cotar.info(path).await.unwrap();
+        assert_eq!(cotar.info_hash(hash).await.unwrap(), entry);
+        let probe = cotar.probe(hash).await.unwrap();
+        assert_eq!(cotar.entry_at(probe.slot).await.unwrap(), entry);
+    }
+}
+
+#[tokio::test]
+async fn test_async_get_many() {
+    use crate::test_util::{create_archive, TEST_FILES};
+
+    let archive = create_archive("async_get_many", &TEST_FILES);
+    let cotar = AsyncCotar::from_tar(archive.embedded()).await.unwrap();
+
+    let paths: Vec<&str> = TEST_FILES.iter().map(|(path, _)| *path).collect();
+    let files = cotar.get_many(&paths, 1024).await.unwrap();
+    for ((_, data), file) in TEST_FILES.iter().zip(files) {
+        assert_eq!(file.unwrap().as_ref(), *data);
+    }
+}
+
+#[tokio::test]
+async fn test_async_shared_reads() {
+    use crate::test_util::{create_archive, TEST_FILES};
+
+    let archive = create_archive("async_shared_reads", &TEST_FILES);
+    let bytes = Bytes::from(std::fs::read(&archive.embedded).unwrap());
+    let cotar = Arc::new(AsyncCotar::from_source(bytes).await.unwrap());
+
+    let handles: Vec<_> = TEST_FILES
+        .iter()
+        .map(|(path, data)| {
+            let cotar = cotar.clone();
+            tokio::spawn(async move {
+                assert_eq!(cotar.get(path).await.unwrap().unwrap().as_ref(), *data);
+            })
+        })
+        .collect();
+
+    for handle in handles {
+        handle.await.unwrap();
+    }
+}
+
+#[tokio::test]
+async fn test_async_entries() {
+    use crate::test_util::{create_archive, TEST_FILES};
+
+    let archive = create_archive("async_entries", &TEST_FILES);
+    let cotar = AsyncCotar::from_tar(archive.embedded()).await.unwrap();
+
+    let mut paths = Vec::new();
+    for slot in 0..cotar.entries {
+        if let Some(entry) = cotar.entry_at(slot).await.unwrap() {
+            paths.push(cotar.read_path(&entry).await.unwrap().unwrap());
+        }
+    }
+    paths.sort();
+    let mut expected: Vec<_> = TEST_FILES.iter().map(|(path, _)| *path).collect();
+    expected.sort();
+    assert_eq!(paths, expected);
+
+    let stat = cotar.stat("README.md").await.unwrap().unwrap();
+    assert_eq!(stat.path, "README.md");
+    assert_eq!(stat.entry_type, crate::CotarEntryType::Regular);
+}
+
+#[cfg(feature = "gzip")]
+#[tokio::test]
+async fn test_async_decode() {
+    use std::io::Write;
+
+    use crate::test_util::create_archive;
+
+    let mut gzip = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    gzip.write_all(b"tile 0/0/0").unwrap();
+    let gzip = gzip.finish().unwrap();
+    let archive = create_archive("async_decode", &[("tiles/0/0/0.pbf.gz", &gzip)]);
+    let mut cotar = AsyncCotar::from_tar(archive.embedded()).await.unwrap();
+
+    cotar.set_decode(true);
+    let path = "tiles/0/0/0.pbf.gz";
+    assert_eq!(
+        cotar.get(path).await.unwrap().unwrap().as_ref(),
+        b"tile 0/0/0"
+    );
+    let files = cotar.get_many(&[path], 0).await.unwrap();
+    assert_eq!(files[0].as_ref().unwrap().as_ref(), b"tile 0/0/0");
+    let (bytes, encoding) = cotar.get_encoded(path).await.unwrap().unwrap();
+    assert_eq!(bytes.as_ref(), gzip);
+    assert_eq!(encoding, Some(ContentEncoding::Gzip));
+}
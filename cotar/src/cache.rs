@@ -0,0 +1,312 @@
+use std::io::Result as IoResult;
+use std::io::{Error, ErrorKind};
+use std::num::NonZeroUsize;
+use std::ops::Range;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use bytes::{Bytes, BytesMut};
+use lru::LruCache;
+
+use crate::source::Source;
+
+/// Default page size of a `CachedSource`, 64KB
+pub const DEFAULT_PAGE_SIZE: u64 = 64 * 1024;
+
+/// Hit and miss counters of a `CachedSource`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    /// Number of pages read from the cache
+    pub hits: u64,
+    /// Number of pages read from the underlying source
+    pub misses: u64,
+}
+
+/// Source wrapper that caches fixed size pages of the underlying source in a bounded LRU cache
+///
+/// Neighbouring index slots and frequently read files are then served from memory, which is
+/// useful for sources where every read has a high latency such as remote archives.
+///
+/// Wrap the cache in a `Arc` to share it between clones of a `Cotar`
+#[derive(Debug)]
+pub struct CachedSource<S> {
+    source: S,
+    page_size: u64,
+    capacity: usize,
+    pages: Mutex<LruCache<u64, Bytes>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl<S> CachedSource<S> {
+    /// Cache up to `capacity` pages of `page_size` bytes
+    ///
+    /// # Panics
+    /// If `page_size` or `capacity` is 0
+    pub fn new(source: S, page_size: u64, capacity: usize) -> Self {
+        assert!(page_size > 0, "Page size must be greater than zero");
+        let capacity = NonZeroUsize::new(capacity).expect("Capacity must be greater than zero");
+
+        CachedSource {
+            source,
+            page_size,
+            capacity: capacity.get(),
+            pages: Mutex::new(LruCache::new(capacity)),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    /// Underlying source
+    pub fn source(&self) -> &S {
+        &self.source
+    }
+
+    pub fn page_size(&self) -> u64 {
+        self.page_size
+    }
+
+    /// Maximum number of pages cached
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Hit and miss counters since the cache was created or last reset
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+        }
+    }
+
+    pub fn reset_stats(&self) {
+        self.hits.store(0, Ordering::Relaxed);
+        self.misses.store(0, Ordering::Relaxed);
+    }
+
+    /// Remove all pages from the cache
+    pub fn clear(&self) {
+        self.pages.lock().unwrap().clear();
+    }
+
+    /// Range of pages that cover `offset..offset+len`, None if the read is too large to be cached
+    fn page_range(&self, offset: u64, len: u64, size: u64) -> IoResult<Option<Range<u64>>> {
+        let end = offset
+            .checked_add(len)
+            .filter(|end| *end <= size)
+            .ok_or_else(|| Error::new(ErrorKind::UnexpectedEof, "failed to fill whole buffer"))?;
+
+        let pages = offset / self.page_size..(end - 1) / self.page_size + 1;
+        // Reads larger than the cache would only evict everything else
+        if pages.end - pages.start > self.capacity as u64 {
+            self.misses
+                .fetch_add(pages.end - pages.start, Ordering::Relaxed);
+            return Ok(None);
+        }
+        Ok(Some(pages))
+    }
+
+    /// Find the cached pages, counting hits
+    fn lookup(&self, pages: &Range<u64>) -> Vec<Option<Bytes>> {
+        let mut cache = self.pages.lock().unwrap();
+        let found: Vec<Option<Bytes>> = pages
+            .clone()
+            .map(|page| cache.get(&page).cloned())
+            .collect();
+        let hits = found.iter().filter(|page| page.is_some()).count() as u64;
+        self.hits.fetch_add(hits, Ordering::Relaxed);
+        found
+    }
+
+    /// Runs of consecutive missing pages as byte ranges of the source, so they can be read at once
+    fn missing(&self, pages: &Range<u64>, found: &[Option<Bytes>], size: u64) -> Vec<Range<u64>> {
+        let mut runs: Vec<Range<u64>> = Vec::new();
+        for (page, bytes) in pages.clone().zip(found) {
+            if bytes.is_some() {
+                continue;
+            }
+            let start = page * self.page_size;
+            let end = (start + self.page_size).min(size);
+            match runs.last_mut() {
+                Some(run) if run.end == start => run.end = end,
+                _ => runs.push(start..end),
+            }
+        }
+        runs
+    }
+
+    /// Split a read of consecutive pages into pages and store them in the cache
+    fn store(&self, pages: &Range<u64>, found: &mut [Option<Bytes>], start: u64, bytes: Bytes) {
+        let mut cache = self.pages.lock().unwrap();
+        let mut offset = 0;
+        while offset < bytes.len() as u64 {
+            let page = (start + offset) / self.page_size;
+            let page_end = (offset + self.page_size).min(bytes.len() as u64);
+            let page_bytes = bytes.slice(offset as usize..page_end as usize);
+            cache.put(page, page_bytes.clone());
+            found[(page - pages.start) as usize] = Some(page_bytes);
+            self.misses.fetch_add(1, Ordering::Relaxed);
+            offset = page_end;
+        }
+    }
+
+    /// Join the pages into the requested range
+    fn assemble(
+        &self,
+        pages: &Range<u64>,
+        found: Vec<Option<Bytes>>,
+        offset: u64,
+        len: u64,
+    ) -> Bytes {
+        let skip = (offset - pages.start * self.page_size) as usize;
+        if found.len() == 1 {
+            let page = found.into_iter().next().flatten().unwrap_or_default();
+            return page.slice(skip..skip + len as usize);
+        }
+
+        let mut output = BytesMut::with_capacity(len as usize);
+        for page in found.into_iter().flatten() {
+            output.extend_from_slice(&page);
+        }
+        output.freeze().slice(skip..skip + len as usize)
+    }
+}
+
+impl<S: Source> Source for CachedSource<S> {
+    fn size(&self) -> u64 {
+        self.source.size()
+    }
+
+    fn read_exact(&self, offset: u64, len: u64) -> IoResult<Bytes> {
+        if len == 0 {
+            return Ok(Bytes::new());
+        }
+        let size = self.source.size();
+        let pages = match self.page_range(offset, len, size)? {
+            None => return self.source.read_exact(offset, len),
+            Some(pages) => pages,
+        };
+
+        let mut found = self.lookup(&pages);
+        for run in self.missing(&pages, &found, size) {
+            let bytes = self.source.read_exact(run.start, run.end - run.start)?;
+            self.store(&pages, &mut found, run.start, bytes);
+        }
+        Ok(self.assemble(&pages, found, offset, len))
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<S: crate::async_cotar::AsyncSource + Sync> crate::async_cotar::AsyncSource
+    for CachedSource<S>
+{
+    fn size(&self) -> u64 {
+        self.source.size()
+    }
+
+    async fn read_exact(&self, offset: u64, len: u64) -> IoResult<Bytes> {
+        if len == 0 {
+            return Ok(Bytes::new());
+        }
+        let size = self.source.size();
+        let pages = match self.page_range(offset, len, size)? {
+            None => return self.source.read_exact(offset, len).await,
+            Some(pages) => pages,
+        };
+
+        let mut found = self.lookup(&pages);
+        for run in self.missing(&pages, &found, size) {
+            let bytes = self
+                .source
+                .read_exact(run.start, run.end - run.start)
+                .await?;
+            self.store(&pages, &mut found, run.start, bytes);
+        }
+        Ok(self.assemble(&pages, found, offset, len))
+    }
+}
+
+#[test]
+fn test_cached_reads() {
+    use crate::test_util::CountingSource;
+
+    let data: Vec<u8> = (0..1000).map(|i| (i % 251) as u8).collect();
+    let cache = CachedSource::new(CountingSource::new(Bytes::from(data.clone())), 64, 4);
+
+    // Reads inside and across page boundaries
+    for (offset, len) in [
+        (0, 10),
+        (10, 54),
+        (60, 10),
+        (100, 200),
+        (990, 10),
+        (0, 1000),
+    ] {
+        let bytes = cache.read_exact(offset, len).unwrap();
+        assert_eq!(
+            bytes.as_ref(),
+            &data[offset as usize..(offset + len) as usize]
+        );
+    }
+    assert!(cache.read_exact(995, 10).is_err());
+}
+
+#[test]
+fn test_cache_stats() {
+    use crate::test_util::CountingSource;
+
+    let cache = CachedSource::new(CountingSource::new(Bytes::from(vec![1; 1000])), 100, 4);
+
+    cache.read_exact(0, 10).unwrap();
+    assert_eq!(cache.stats(), CacheStats { hits: 0, misses: 1 });
+
+    // Same page
+    cache.read_exact(50, 10).unwrap();
+    assert_eq!(cache.stats(), CacheStats { hits: 1, misses: 1 });
+
+    // Spans a cached and a missing page
+    cache.read_exact(90, 20).unwrap();
+    assert_eq!(cache.stats(), CacheStats { hits: 2, misses: 2 });
+    assert_eq!(cache.source().reads(), 2);
+
+    // Consecutive missing pages are read at once
+    cache.read_exact(200, 300).unwrap();
+    assert_eq!(cache.stats(), CacheStats { hits: 2, misses: 5 });
+    assert_eq!(cache.source().reads(), 3);
+
+    // Page 0 has been evicted
+    cache.read_exact(0, 10).unwrap();
+    assert_eq!(cache.stats(), CacheStats { hits: 2, misses: 6 });
+
+    // Too large to cache
+    cache.read_exact(0, 1000).unwrap();
+    assert_eq!(
+        cache.stats(),
+        CacheStats {
+            hits: 2,
+            misses: 16
+        }
+    );
+
+    cache.reset_stats();
+    assert_eq!(cache.stats(), CacheStats::default());
+}
+
+#[test]
+fn test_cotar_cached() {
+    use crate::test_util::{create_archive, TEST_FILES};
+    use crate::Cotar;
+    use std::sync::Arc;
+
+    let archive = create_archive("cotar_cached", &TEST_FILES);
+    let source = Arc::new(CachedSource::new(
+        crate::DataView::open(archive.embedded()).unwrap(),
+        DEFAULT_PAGE_SIZE,
+        16,
+    ));
+    let cotar = Cotar::from_source(source.clone()).unwrap();
+
+    crate::test_util::assert_test_files(&cotar);
+    // The entire test archive fits in a single page
+    assert_eq!(source.stats().misses, 1);
+}
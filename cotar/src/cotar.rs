@@ -1,176 +1,570 @@
-use std::io::Result as IoResult;
-use std::io::{Error, ErrorKind};
+use crate::error::CotarResult;
 
-use bytes::{Buf, Bytes};
+use bytes::Bytes;
+use cotar_core::Index;
+pub use cotar_core::{
+    fnv1a_64, header_size, index_entry_size, CotarHeader, CotarIndexEntry, HashAlgorithm,
+    ProbeResult, COTAR_V1_HEADER_MAGIC, COTAR_V1_INDEX_ENTRY_SIZE, COTAR_V2_HEADER_MAGIC,
+    COTAR_V2_HEADER_SIZE, COTAR_V2_INDEX_ENTRY_SIZE, COTAR_V3_HEADER_MAGIC, COTAR_V3_HEADER_SIZE,
+    COTAR_V3_INDEX_ENTRY_SIZE,
+};
+use tracing::{debug, instrument, trace};
 
-use crate::dataview;
-use crate::fnv1a;
+use crate::archive::{self, coalesce_reads, ArchiveState, PathLookup};
+use crate::dataview::DataView;
+use crate::encoding::ContentEncoding;
+use crate::path_table::{self, PathTable, COTAR_PATH_TABLE_HEADER_SIZE};
+use crate::reader::EntryReader;
+use crate::source::Source;
+use crate::stat::CotarStat;
+use crate::tar_header::{header_range, TAR_EXTENSION_LOOKBACK};
 
-/// V2 cotar files have a fixed header and entry size
-pub const COTAR_V2_HEADER_SIZE: u64 = 8;
-pub const COTAR_V2_INDEX_ENTRY_SIZE: u64 = 16;
-
-/// "COT\x02" as a u32
-pub const COTAR_V2_HEADER_MAGIC: u32 = 39079747;
-
-#[derive(Debug)] // TODO None of these need to be 64bits
-pub struct CotarIndexEntry {
-    /// FNV1A Hash of the file_name
-    pub hash: u64,
-    /// File offset
-    pub file_offset: u64,
-    /// File size
-    pub file_size: u32,
-}
-
-#[derive(Debug)]
-pub struct Cotar {
+/// Cotar archive reader
+///
+/// All reads are positional so a `Cotar` can be shared between threads,
+/// cloning is cheap as the underlying files are reference counted
+#[derive(Debug, Clone)]
+pub struct Cotar<S = DataView> {
     /// Cotar index version generally v2
     pub version: u8,
+    /// Algorithm used to hash file paths
+    pub hash_algorithm: HashAlgorithm,
     /// Number of entries in the tar archive
     pub entries: u64,
     /// Offset in the view for the index
     pub index_offset: u64,
-    /// View of the
-    pub view: dataview::DataView,
+    /// View of the tar archive
+    pub view: S,
     /// Index reference if the index is a separate file
-    pub view_index: Option<dataview::DataView>,
+    pub view_index: Option<S>,
+    /// Index state shared with `AsyncCotar`
+    state: ArchiveState,
 }
 
-pub struct CotarHeader {
-    /// Cotar magic generally "COT\x02"
-    pub magic: u32,
-    /// Cotar index version generally v2
-    pub version: u8,
-    /// Number of entries in the tar archive
-    pub entries: u32,
+impl Cotar {
+    /// Parse the magic and slot count of a index header
+    pub fn header_from_bytes(header_bytes: Bytes) -> CotarResult<CotarHeader> {
+        Ok(CotarHeader::from_bytes(&header_bytes)?)
+    }
+
+    /// Load a cotar from a packed tar file
+    ///
+    /// The index of the tar must be the final bytes of the tar file
+    #[instrument(level = "debug")]
+    pub fn from_tar(file_name: &str) -> CotarResult<Self> {
+        Cotar::from_source(DataView::open(file_name)?)
+    }
+
+    /// Load a cotar from a tar and index file
+    #[instrument(level = "debug")]
+    pub fn from_tar_index(tar_file_name: &str, index_file_name: &str) -> CotarResult<Self> {
+        Cotar::from_sources(
+            DataView::open(tar_file_name)?,
+            DataView::open(index_file_name)?,
+        )
+    }
+
+    /// Load a cotar from a packed tar file using a memory map
+    ///
+    /// Reads are returned as zero-copy slices of the mapping
+    #[cfg(feature = "mmap")]
+    #[instrument(level = "debug")]
+    pub fn from_tar_mmap(file_name: &str) -> CotarResult<Self> {
+        Cotar::from_source(DataView::open_mmap(file_name)?)
+    }
+
+    /// Load a cotar from a tar and index file using memory maps
+    #[cfg(feature = "mmap")]
+    #[instrument(level = "debug")]
+    pub fn from_tar_index_mmap(tar_file_name: &str, index_file_name: &str) -> CotarResult<Self> {
+        Cotar::from_sources(
+            DataView::open_mmap(tar_file_name)?,
+            DataView::open_mmap(index_file_name)?,
+        )
+    }
+
+    /// Generate a hash of a file path with the default fnv1a algorithm
+    ///
+    /// Archives can use other algorithms, see `Cotar::hash_algorithm`
+    pub fn hash(path: &str) -> u64 {
+        fnv1a_64(path.as_bytes())
+    }
 }
 
-impl Cotar {
-    pub fn header_from_bytes(mut header_bytes: Bytes) -> IoResult<CotarHeader> {
-        if header_bytes.len() < COTAR_V2_HEADER_SIZE as usize {
-            return Err(Error::new(ErrorKind::Other, "Invalid header length"));
+impl<S: Source> Cotar<S> {
+    /// Load a cotar from a source containing a packed tar
+    ///
+    /// The index of the tar must be the final bytes of the source
+    pub fn from_source(view: S) -> CotarResult<Self> {
+        Cotar::from_source_with_tail(view, 0)
+    }
+
+    /// Load a cotar from a source containing a packed tar, reading the last `tail_size` bytes
+    /// of the source with a single read
+    ///
+    /// The index slots in the tail are kept in memory so lookups that land in them do not read the
+    /// source, if the tail covers the entire index it is loaded as if by `load_index`.
+    /// Only the footer is read when `tail_size` is smaller than it, see `DEFAULT_TAIL_SIZE`
+    #[instrument(level = "debug", skip(view))]
+    pub fn from_source_with_tail(view: S, tail_size: u64) -> CotarResult<Self> {
+        let size = view.size();
+        let len = archive::tail_len(size, tail_size)?;
+        let tail = view.read_exact(size - len, len)?;
+        let (index, state) = ArchiveState::embedded(size, tail, tail_size)?;
+        debug!(
+            version = index.header.version,
+            entries = index.header.entries,
+            index_offset = index.index_offset,
+            index_loaded = state.index_bytes.is_some(),
+            "Cotar opened"
+        );
+        Ok(Cotar::new(index, state, view, None))
+    }
+
+    /// Load a cotar from a source containing a tar and a source containing its index
+    #[instrument(level = "debug", skip_all)]
+    pub fn from_sources(view: S, view_index: S) -> CotarResult<Self> {
+        let size = view_index.size();
+        let head = view_index.read_exact(0, archive::head_len(size)?)?;
+        let (index, state) = ArchiveState::separate(&head, size, view.size())?;
+        debug!(
+            version = index.header.version,
+            entries = index.header.entries,
+            "Cotar opened with separate index"
+        );
+        Ok(Cotar::new(index, state, view, Some(view_index)))
+    }
+
+    fn new(index: Index, state: ArchiveState, view: S, view_index: Option<S>) -> Self {
+        Cotar {
+            version: index.header.version,
+            hash_algorithm: index.header.hash_algorithm,
+            entries: index.entries(),
+            index_offset: index.index_offset,
+            view,
+            view_index,
+            state,
         }
-        let magic = header_bytes.get_u32_le();
-        if magic != COTAR_V2_HEADER_MAGIC {
-            return Err(Error::new(ErrorKind::Other, "Invalid magic"));
+    }
+
+    /// Size of the index in bytes including the header and footer
+    pub fn index_size(&self) -> u64 {
+        self.index().header.index_size()
+    }
+
+    /// Read the entire index into memory
+    ///
+    /// By default the index is read lazily one slot at a time, once loaded lookups
+    /// only read the file contents from the source
+    ///
+    /// The header and footer of the loaded index are checked to match
+    pub fn load_index(&mut self) -> CotarResult<()> {
+        let view_index = self.view_index.as_ref().unwrap_or(&self.view);
+        let bytes = view_index.read_exact(self.index_offset, self.index_size())?;
+        let index = self.index();
+        self.state.set_index_bytes(&index, bytes)
+    }
+
+    /// Has the index been loaded into memory with `load_index`
+    pub fn is_index_loaded(&self) -> bool {
+        self.state.index_bytes.is_some()
+    }
+
+    /// Read the path table stored next to the index
+    ///
+    /// Returns None if the archive does not have a path table
+    pub fn read_path_table(&self) -> CotarResult<Option<PathTable>> {
+        let range = match &self.view_index {
+            None => match self.state.table_frame_offset(&self.index()) {
+                // The table in front of a embedded index is found when opening if the tail
+                // covers its footer
+                None => self.state.path_table_range,
+                Some(offset) => {
+                    let frame = self.view.read_exact(offset, COTAR_PATH_TABLE_HEADER_SIZE)?;
+                    path_table::table_range(frame, offset, self.state.data_size, false)?
+                }
+            },
+            Some(view_index) => {
+                let size = view_index.size();
+                match path_table::frame_offset(self.index_offset, self.index_size(), size, true) {
+                    Some(offset) => {
+                        let frame = view_index.read_exact(offset, COTAR_PATH_TABLE_HEADER_SIZE)?;
+                        path_table::table_range(frame, offset, size, true)?
+                    }
+                    None => None,
+                }
+            }
+        };
+        let Some((offset, len)) = range else {
+            return Ok(None);
+        };
+        let view_index = self.view_index.as_ref().unwrap_or(&self.view);
+        let bytes = view_index.read_exact(offset, len)?;
+        Ok(Some(PathTable::from_bytes(bytes, self.entries)?))
+    }
+
+    /// Read the path table into memory
+    ///
+    /// Once loaded lookups compare the path against the table, so files with colliding hashes
+    /// are never returned for the wrong path and no tar headers need to be read to verify paths.
+    ///
+    /// Files pointing into a path table in front of a embedded index are out of bounds once it
+    /// has been found, by `load_path_table` or on open when the tail covers its footer.
+    ///
+    /// Returns false if the archive does not have a path table
+    pub fn load_path_table(&mut self) -> CotarResult<bool> {
+        if let Some(offset) = self.state.table_frame_offset(&self.index()) {
+            let frame = self.view.read_exact(offset, COTAR_PATH_TABLE_HEADER_SIZE)?;
+            self.state.set_table_frame(offset, frame)?;
         }
+        self.state.path_table = self.read_path_table()?;
+        Ok(self.state.path_table.is_some())
+    }
 
-        let entries = header_bytes.get_u32_le();
+    /// Path table loaded with `load_path_table`
+    ///
+    /// Lists the paths of every file in the archive
+    pub fn path_table(&self) -> Option<&PathTable> {
+        self.state.path_table.as_ref()
+    }
 
-        Ok(CotarHeader {
-            magic,
-            version: 2,
-            entries,
-        })
+    /// Location of the index for lookups with `cotar_core`
+    fn index(&self) -> Index {
+        Index {
+            header: CotarHeader::new(self.version, self.entries as u32, self.hash_algorithm),
+            index_offset: self.index_offset,
+            data_size: self.state.data_size,
+        }
     }
 
-    /// Load a cotar from a packed tar file
+    /// Limit the size of files that `get` and `get_many` will read into memory
     ///
-    /// The index of the tar must be the final bytes of the tar file
-    pub fn from_tar(file_name: &str) -> IoResult<Self> {
-        let mut view = dataview::DataView::open(file_name)?;
-        let header_bytes =
-            view.read_exact(view.size - COTAR_V2_HEADER_SIZE, COTAR_V2_HEADER_SIZE)?;
+    /// Larger files return a `FileTooLarge` error, use `reader` to stream them instead.
+    /// Defaults to no limit other than the size of the tar
+    pub fn set_max_file_size(&mut self, max_file_size: u64) {
+        self.state.max_file_size = max_file_size;
+    }
 
-        let header = Cotar::header_from_bytes(header_bytes)?;
+    /// Decode gzip, zstd and brotli files returned from `get` and `get_many`
+    ///
+    /// Encodings are detected with `ContentEncoding::detect`, decoding an encoding needs its
+    /// `gzip`, `zstd` or `brotli` feature otherwise a `UnsupportedEncoding` error is returned.
+    /// Decoded files are also limited by `set_max_file_size`.
+    ///
+    /// Use `get_encoded` to read the raw bytes and pass the encoding through to HTTP clients
+    pub fn set_decode(&mut self, decode: bool) {
+        self.state.decode = decode;
+    }
 
-        let index_offset = view.size - 16 - (header.entries as u64) * COTAR_V2_INDEX_ENTRY_SIZE;
+    /// Read a index slot at a offset relative to the start of the index
+    fn read_slot(&self, offset: u64) -> CotarResult<Bytes> {
+        let index = self.index();
+        match self.state.cached_slot(&index, offset)? {
+            Some(bytes) => Ok(bytes),
+            None => {
+                let view_index = self.view_index.as_ref().unwrap_or(&self.view);
+                let len = index_entry_size(self.version);
+                Ok(view_index.read_exact(self.index_offset + offset, len)?)
+            }
+        }
+    }
 
-        Ok(Cotar {
-            version: header.version,
-            entries: header.entries as u64,
-            index_offset,
-            view,
-            view_index: None,
-        })
+    /// Read the entry stored in a slot of the index
+    ///
+    /// Returns None if the slot is empty, entries that point outside of the tar return a
+    /// `OutOfBounds` error
+    pub fn entry_at(&self, slot: u64) -> CotarResult<Option<CotarIndexEntry>> {
+        let index = self.index();
+        let Some(offset) = index.slot_offset(slot) else {
+            return Ok(None);
+        };
+        Ok(index.entry(&self.read_slot(offset)?)?)
     }
 
-    /// Load a cotar from a tar and index file
-    pub fn from_tar_index(tar_file_name: &str, index_file_name: &str) -> IoResult<Self> {
-        let mut view_index = dataview::DataView::open(index_file_name)?;
+    /// Iterate every entry in the index in slot order
+    ///
+    /// Every slot is read one at a time, use `load_index` first to avoid a read per slot
+    pub fn iter(&self) -> CotarEntries<'_, S> {
+        CotarEntries {
+            cotar: self,
+            slot: 0,
+        }
+    }
 
-        let header_bytes = view_index.read_exact(0, COTAR_V2_INDEX_ENTRY_SIZE)?;
-        let header = Cotar::header_from_bytes(header_bytes)?;
+    /// Recover the path of a entry from the tar header in front of the file
+    ///
+    /// Returns None if the header cannot be parsed or the path is not UTF-8.
+    /// Files stored as hard links return the path of the file they link to
+    pub fn read_path(&self, entry: &CotarIndexEntry) -> CotarResult<Option<String>> {
+        let (offset, len) = match header_range(entry.file_offset, TAR_EXTENSION_LOOKBACK) {
+            Ok(range) => range,
+            Err(_) => return Ok(None),
+        };
+        Ok(archive::header_path(&self.view.read_exact(offset, len)?))
+    }
 
-        let index_offset = 0;
+    /// Read the metadata of a file from the tar header in front of it
+    ///
+    /// Returns None if the file is not found
+    pub fn stat(&self, path: &str) -> CotarResult<Option<CotarStat>> {
+        match self.info(path)? {
+            None => Ok(None),
+            Some(entry) => Ok(Some(self.stat_entry(&entry)?)),
+        }
+    }
 
-        Ok(Cotar {
-            version: header.version,
-            entries: header.entries as u64,
-            index_offset,
-            view_index: Some(view_index),
-            view: dataview::DataView::open(tar_file_name)?,
-        })
+    /// Read the metadata of a entry from the tar header in front of the file
+    pub fn stat_entry(&self, entry: &CotarIndexEntry) -> CotarResult<CotarStat> {
+        let (offset, len) = header_range(entry.file_offset, TAR_EXTENSION_LOOKBACK)?;
+        archive::stat_entry(entry, &self.view.read_exact(offset, len)?)
     }
 
-    /// Generate a hash of a file path
+    /// Read a file from the tar archive
     ///
-    /// Uses fnv1a by default
-    pub fn hash(path: &str) -> u64 {
-        return fnv1a::fnv1a_64(path.as_bytes());
+    /// Compressed files are decoded if enabled with `set_decode`
+    ///
+    /// Returns None if the file is not found
+    pub fn get(&self, path: &str) -> CotarResult<Option<Bytes>> {
+        match self.read_file(path)? {
+            None => Ok(None),
+            Some(bytes) => Ok(Some(self.state.decode_file(path, bytes)?)),
+        }
     }
 
-    /// Read the raw bytes of a file from the tar archive
+    /// Read the raw bytes of a file with the encoding detected from its path and magic bytes
+    ///
+    /// Files are never decoded, so the bytes can be sent with a HTTP `Content-Encoding` header
     ///
     /// Returns None if the file is not found
-    pub fn get(&mut self, path: &str) -> IoResult<Option<Bytes>> {
+    pub fn get_encoded(&self, path: &str) -> CotarResult<Option<(Bytes, Option<ContentEncoding>)>> {
+        Ok(self.read_file(path)?.map(|bytes| {
+            let encoding = ContentEncoding::detect(path, &bytes);
+            (bytes, encoding)
+        }))
+    }
+
+    /// Read the raw bytes of a file from the tar archive
+    fn read_file(&self, path: &str) -> CotarResult<Option<Bytes>> {
         let info = self.info(path)?;
 
         match info {
             None => Ok(None),
             Some(entry) => {
-                let bytes = self
-                    .view
-                    .read_exact(entry.file_offset, entry.file_size as u64)?;
+                self.state.check_file_size(&entry)?;
+                trace!(
+                    path,
+                    file_offset = entry.file_offset,
+                    file_size = entry.file_size,
+                    "Read file"
+                );
+                let bytes = self.view.read_exact(entry.file_offset, entry.file_size)?;
                 Ok(Some(bytes))
             }
         }
     }
+
+    /// Open a reader over a file in the tar archive
+    ///
+    /// Unlike `get` the file is not read into memory, which allows large files to be streamed
+    ///
+    /// Returns None if the file is not found
+    pub fn reader(&self, path: &str) -> CotarResult<Option<EntryReader<'_, S>>> {
+        let info = self.info(path)?;
+        Ok(info.map(|entry| EntryReader::new(&self.view, &entry)))
+    }
+
+    /// Read the raw bytes of many files from the tar archive
+    ///
+    /// All index entries are resolved first, then files that are within `max_gap` bytes of each other
+    /// in the tar are read together with a single read. Merged reads are limited to
+    /// `MAX_MERGED_READ_SIZE` bytes or the size set with `set_max_file_size` if it is smaller.
+    ///
+    /// Compressed files are decoded if enabled with `set_decode`
+    ///
+    /// Returns the files in the same order as `paths`, None for files that are not found
+    pub fn get_many(&self, paths: &[&str], max_gap: u64) -> CotarResult<Vec<Option<Bytes>>> {
+        let mut entries = Vec::with_capacity(paths.len());
+        for path in paths {
+            let entry = self.info(path)?;
+            if let Some(entry) = &entry {
+                self.state.check_file_size(entry)?;
+            }
+            entries.push(entry);
+        }
+
+        let mut output = vec![None; paths.len()];
+        for range in coalesce_reads(&entries, max_gap, self.state.max_merged_read()) {
+            trace!(offset = range.offset, len = range.len, "Read file range");
+            let bytes = self.view.read_exact(range.offset, range.len)?;
+            range.split(bytes, &mut output);
+        }
+        self.state.decode_files(paths, &mut output)?;
+        Ok(output)
+    }
+
     /// Read the metadata entry for a file path
     ///
     /// Returns None if file is not found
-    pub fn info(&mut self, path: &str) -> IoResult<Option<CotarIndexEntry>> {
-        let hash = Cotar::hash(path);
+    ///
+    /// When a path table is loaded the path of the slot is checked against `path`, otherwise when
+    /// path verification is enabled the tar header of the file is checked against `path`,
+    /// if no entry matches but the hash was found an error is returned
+    ///
+    /// Entries that point outside of the tar return a `OutOfBounds` error
+    pub fn info(&self, path: &str) -> CotarResult<Option<CotarIndexEntry>> {
+        let index = self.index();
+        let mut lookup = self.state.lookup(&index, path);
+        while let Some(offset) = lookup.offset() {
+            if let Some(entry) = lookup.step(&self.read_slot(offset)?)? {
+                let matched = self.verify_path(&lookup, path, &entry)?;
+                lookup.verify(matched);
+            }
+        }
+        lookup.finish()
+    }
 
-        let entries = self.entries as u64;
-        let start_index = hash % entries;
-        let mut index = start_index;
+    /// Read the metadata entry for a path hash, hashed with the archive's `hash_algorithm`
+    ///
+    /// Paths are not verified, so with duplicate hashes the first entry in the index is returned
+    ///
+    /// Returns None if the hash is not found
+    pub fn info_hash(&self, hash: u64) -> CotarResult<Option<CotarIndexEntry>> {
+        Ok(self.probe(hash)?.entry)
+    }
 
-        let view_index = self.view_index.as_mut().unwrap_or(&mut self.view);
+    /// Probe the index for a path hash, reporting the slot the entry is in and how far it is from
+    /// the slot the hash starts probing at
+    ///
+    /// Indexes without any slots report slot 0
+    pub fn probe(&self, hash: u64) -> CotarResult<ProbeResult> {
+        let mut lookup = self.index().lookup(hash);
+        while let Some(offset) = lookup.offset() {
+            lookup.step(&self.read_slot(offset)?)?;
+        }
+        let result = lookup.result();
+        trace!(
+            hash,
+            slot = result.slot,
+            distance = result.distance,
+            "Hash probe"
+        );
+        Ok(result)
+    }
 
-        loop {
-            let offset =
-                self.index_offset + index * COTAR_V2_INDEX_ENTRY_SIZE + COTAR_V2_HEADER_SIZE;
+    /// Verify paths on lookup by reading the tar header in front of each file found
+    ///
+    /// Guards against two paths with the same hash returning the wrong file.
+    /// Files stored as hard links point at the header of the file they link to,
+    /// they are verified by looking up the linked file and checking it has the same entry.
+    /// Paths in extension headers too large to read return a `UnverifiedPath` error
+    pub fn set_verify_paths(&mut self, verify: bool) {
+        self.state.verify_paths = verify;
+    }
 
-            let mut bytes = view_index.read_exact(offset, COTAR_V2_INDEX_ENTRY_SIZE)?;
-            let start_hash = bytes.get_u64_le();
-            // Null entry file is missing
-            if start_hash == 0 {
-                return Ok(None);
+    /// Does the tar header of the entry match the path, or is the path a hard link to the file
+    /// named by the tar header
+    fn verify_path(
+        &self,
+        lookup: &PathLookup<'_>,
+        path: &str,
+        entry: &CotarIndexEntry,
+    ) -> CotarResult<bool> {
+        let mut lookback = 0;
+        loop {
+            let (offset, len) = header_range(entry.file_offset, lookback)?;
+            let header = archive::parse_header(entry, &self.view.read_exact(offset, len)?)?;
+            if header.path_bytes().as_ref() == path.as_bytes() {
+                return Ok(true);
             }
-
-            if start_hash == hash {
-                return Ok(Some(CotarIndexEntry {
-                    hash,
-                    file_offset: (bytes.get_u32_le() as u64) * 512,
-                    file_size: bytes.get_u32_le(),
-                }));
+            // Hard links are checked once extension headers have been searched for
+            if lookback == TAR_EXTENSION_LOOKBACK {
+                if let Some(target) = lookup.link_target(&header.path_bytes()) {
+                    let target = self.probe(target)?.entry;
+                    if target.is_some_and(|target| archive::same_file(entry, &target)) {
+                        return Ok(true);
+                    }
+                }
             }
-
-            index += 1;
-            // Loop around to the start of the hash table
-            if index >= entries {
-                index = 0;
+            match archive::next_lookback(lookback, offset, &header, path)? {
+                Some(next) => lookback = next,
+                None => return Ok(false),
             }
-            // Looped full around nothing to find here
-            if index == start_index {
-                return Ok(None);
+        }
+    }
+}
+
+/// Iterator over the entries of a index, created with `Cotar::iter`
+///
+/// Stops after the first error
+pub struct CotarEntries<'a, S> {
+    cotar: &'a Cotar<S>,
+    slot: u64,
+}
+
+impl<'a, S: Source> CotarEntries<'a, S> {
+    /// Also recover the path of every entry
+    ///
+    /// Paths come from the path table when one is loaded, otherwise from the tar header in front
+    /// of each file, see `Cotar::read_path`
+    pub fn with_paths(self) -> CotarEntryPaths<'a, S> {
+        CotarEntryPaths { entries: self }
+    }
+
+    /// Slot and entry of the next populated slot
+    fn next_slot(&mut self) -> Option<CotarResult<(u64, CotarIndexEntry)>> {
+        while self.slot < self.cotar.entries {
+            let slot = self.slot;
+            self.slot += 1;
+            match self.cotar.entry_at(slot) {
+                Ok(Some(entry)) => return Some(Ok((slot, entry))),
+                Ok(None) => {}
+                Err(e) => {
+                    self.slot = self.cotar.entries;
+                    return Some(Err(e));
+                }
             }
         }
+        None
     }
 }
 
+impl<S: Source> Iterator for CotarEntries<'_, S> {
+    type Item = CotarResult<CotarIndexEntry>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_slot().map(|entry| entry.map(|(_, entry)| entry))
+    }
+}
+
+/// Iterator over the entries of a index and their paths, created with `CotarEntries::with_paths`
+///
+/// The path is None if it could not be recovered
+pub struct CotarEntryPaths<'a, S> {
+    entries: CotarEntries<'a, S>,
+}
+
+impl<S: Source> Iterator for CotarEntryPaths<'_, S> {
+    type Item = CotarResult<(CotarIndexEntry, Option<String>)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let cotar = self.entries.cotar;
+        let next = self.entries.next_slot()?;
+        Some(next.and_then(|(slot, entry)| {
+            let path = match &cotar.state.path_table {
+                Some(table) => table.path(slot).map(|path| path.to_string()),
+                None => cotar.read_path(&entry)?,
+            };
+            Ok((entry, path))
+        }))
+    }
+}
+
+/// Tail size that covers the index of archives with up to a few thousand files
+pub const DEFAULT_TAIL_SIZE: u64 = 64 * 1024;
+
+/// Largest read that `get_many` merges nearby files into
+pub const MAX_MERGED_READ_SIZE: u64 = 16 * 1024 * 1024;
+
 #[test]
 
 fn test_header() {
@@ -179,16 +573,27 @@ fn test_header() {
 
     assert_eq!(header.magic, COTAR_V2_HEADER_MAGIC);
     assert_eq!(header.entries, 28);
-    // assert_eq!(header.version, 2);
+    assert_eq!(header.version, 2);
 }
 
 #[test]
-fn test_header_invalid_version() {
-    // Set version to 0x01
+fn test_header_v1() {
     let buf = Bytes::from(vec![0x43, 0x4f, 0x54, 0x01, 0x1c, 0x00, 0x00, 0x00, 0x28]);
+    let header = Cotar::header_from_bytes(buf).unwrap();
+
+    assert_eq!(header.magic, COTAR_V1_HEADER_MAGIC);
+    assert_eq!(header.version, 1);
+    assert_eq!(header.entries, 28);
+    assert_eq!(header.index_size(), 16 + 28 * 24);
+}
+
+#[test]
+fn test_header_invalid_version() {
+    // Set version to 0x05
+    let buf = Bytes::from(vec![0x43, 0x4f, 0x54, 0x05, 0x1c, 0x00, 0x00, 0x00, 0x28]);
     let header = Cotar::header_from_bytes(buf);
 
-    assert_eq!(header.is_ok(), false)
+    assert!(header.is_err())
 }
 #[test]
 fn test_header_invalid_magic() {
@@ -196,5 +601,720 @@ fn test_header_invalid_magic() {
     let buf = Bytes::from(vec![0x41, 0x4f, 0x54, 0x02, 0x1c, 0x00, 0x00, 0x00, 0x28]);
     let header = Cotar::header_from_bytes(buf);
 
-    assert_eq!(header.is_ok(), false)
+    assert!(header.is_err())
+}
+
+#[test]
+fn test_cotar_send_sync() {
+    fn assert_send_sync<T: Send + Sync + Clone>() {}
+    assert_send_sync::<Cotar>();
+}
+
+#[test]
+fn test_from_tar_get() {
+    let archive = crate::test_util::create_archive("from_tar_get", &crate::test_util::TEST_FILES);
+
+    for cotar in [
+        Cotar::from_tar(archive.embedded()).unwrap(),
+        Cotar::from_tar_index(archive.tar(), archive.index()).unwrap(),
+    ] {
+        crate::test_util::assert_test_files(&cotar);
+    }
+}
+
+#[test]
+fn test_shared_reads() {
+    let archive = crate::test_util::create_archive("shared_reads", &crate::test_util::TEST_FILES);
+    let cotar = Cotar::from_tar(archive.embedded()).unwrap();
+
+    let handles: Vec<_> = (0..8)
+        .map(|_| {
+            let cotar = cotar.clone();
+            std::thread::spawn(move || {
+                for _ in 0..100 {
+                    crate::test_util::assert_test_files(&cotar);
+                }
+            })
+        })
+        .collect();
+
+    for handle in handles {
+        handle.join().unwrap();
+    }
+}
+
+#[cfg(feature = "mmap")]
+#[test]
+fn test_from_tar_mmap_get() {
+    let archive =
+        crate::test_util::create_archive("from_tar_mmap_get", &crate::test_util::TEST_FILES);
+
+    for cotar in [
+        Cotar::from_tar_mmap(archive.embedded()).unwrap(),
+        Cotar::from_tar_index_mmap(archive.tar(), archive.index()).unwrap(),
+    ] {
+        crate::test_util::assert_test_files(&cotar);
+    }
+}
+
+#[test]
+fn test_load_index() {
+    use crate::test_util::{create_archive, CountingSource, TEST_FILES};
+
+    let archive = create_archive("load_index", &TEST_FILES);
+    let tar = Bytes::from(std::fs::read(&archive.tar).unwrap());
+    let index = Bytes::from(std::fs::read(&archive.index).unwrap());
+
+    let mut cotar =
+        Cotar::from_sources(CountingSource::new(tar), CountingSource::new(index)).unwrap();
+    assert!(!cotar.is_index_loaded());
+    cotar.load_index().unwrap();
+    assert!(cotar.is_index_loaded());
+
+    let view_index = cotar.view_index.as_ref().unwrap();
+    let index_reads = view_index.reads();
+    crate::test_util::assert_test_files(&cotar);
+
+    // Lookups should only read the file contents
+    assert_eq!(view_index.reads(), index_reads);
+    assert_eq!(cotar.view.reads(), TEST_FILES.len());
+}
+
+#[test]
+fn test_from_source_with_tail() {
+    use crate::test_util::{create_archive, CountingSource, TEST_FILES};
+
+    let archive = create_archive("from_source_with_tail", &TEST_FILES);
+    let embedded = Bytes::from(std::fs::read(archive.embedded()).unwrap());
+
+    // The whole index fits in the tail so it is loaded with the single read on open
+    let cotar =
+        Cotar::from_source_with_tail(CountingSource::new(embedded.clone()), DEFAULT_TAIL_SIZE)
+            .unwrap();
+    assert!(cotar.is_index_loaded());
+    assert_eq!(cotar.view.reads(), 1);
+    crate::test_util::assert_test_files(&cotar);
+    assert_eq!(cotar.view.reads(), 1 + TEST_FILES.len());
+
+    // Only the last two slots fit in the tail, the others are read when they are looked up
+    let entry_size = index_entry_size(cotar.version);
+    let tail_size = header_size(cotar.version) + entry_size * 2;
+    let cotar =
+        Cotar::from_source_with_tail(CountingSource::new(embedded.clone()), tail_size).unwrap();
+    assert!(!cotar.is_index_loaded());
+    for slot in 0..cotar.entries {
+        cotar.entry_at(slot).unwrap();
+    }
+    assert_eq!(cotar.view.reads() as u64, 1 + cotar.entries - 2);
+    crate::test_util::assert_test_files(&cotar);
+
+    // Tails smaller than the footer only read the footer
+    let cotar = Cotar::from_source_with_tail(CountingSource::new(embedded), 0).unwrap();
+    assert!(!cotar.is_index_loaded());
+    assert_eq!(cotar.view.reads(), 1);
+    cotar.entry_at(cotar.entries - 1).unwrap();
+    assert_eq!(cotar.view.reads(), 2);
+}
+
+#[test]
+fn test_probe_hash() {
+    use crate::test_util::{create_archive, TEST_FILES};
+    use crate::CotarIndex;
+
+    let archive = create_archive("probe_hash", &TEST_FILES);
+    let cotar = Cotar::from_tar(archive.embedded()).unwrap();
+    for (path, _) in TEST_FILES {
+        let hash = cotar.hash_algorithm.hash(path.as_bytes());
+        let entry = cotar.info(path).unwrap();
+        assert_eq!(cotar.info_hash(hash).unwrap(), entry);
+
+        let probe = cotar.probe(hash).unwrap();
+        assert_eq!(probe.entry, entry);
+        assert_eq!(cotar.entry_at(probe.slot).unwrap(), entry);
+        assert_eq!(
+            probe.distance,
+            (probe.slot + cotar.entries - hash % cotar.entries) % cotar.entries
+        );
+    }
+    let missing = cotar.probe(Cotar::hash("tiles/2/0/0.pbf")).unwrap();
+    assert_eq!(missing.entry, None);
+    assert_eq!(cotar.entry_at(missing.slot).unwrap(), None);
+
+    // Two hashes that start at slot 0 of a two slot index
+    let mut index = CotarIndex::new();
+    for (hash, file_offset) in [(4, 0), (6, 512)] {
+        let entry = CotarIndexEntry {
+            hash,
+            file_offset,
+            file_size: 4,
+        };
+        index.entries.insert(hash, entry);
+    }
+    let mut bytes = vec![0; 1024];
+    bytes.extend_from_slice(&index.pack(1.0).unwrap().vec);
+    let cotar = Cotar::from_source(Bytes::from(bytes)).unwrap();
+    let first = cotar.probe(4).unwrap();
+    assert_eq!((first.slot, first.distance), (0, 0));
+    let second = cotar.probe(6).unwrap();
+    assert_eq!((second.slot, second.distance), (1, 1));
+    assert_eq!(second.entry.unwrap().file_offset, 512);
+    // Every slot is full so a missing hash checks the whole index
+    let missing = cotar.probe(8).unwrap();
+    assert_eq!(
+        (missing.entry, missing.slot, missing.distance),
+        (None, 1, 1)
+    );
+}
+
+#[test]
+fn test_get_many() {
+    use crate::test_util::{create_archive, CountingSource, TEST_FILES};
+
+    let archive = create_archive("get_many", &TEST_FILES);
+    let tar = Bytes::from(std::fs::read(&archive.tar).unwrap());
+    let index = Bytes::from(std::fs::read(&archive.index).unwrap());
+    let cotar = Cotar::from_sources(CountingSource::new(tar), CountingSource::new(index)).unwrap();
+
+    let paths = [
+        "tiles/1/1/0.pbf",
+        "tiles/2/0/0.pbf",
+        "tiles/0/0/0.pbf",
+        "tiles/1/0/0.pbf",
+        "tiles/0/0/0.pbf",
+    ];
+
+    // Files are separated by their 512 byte tar headers
+    let files = cotar.get_many(&paths, 0).unwrap();
+    assert_eq!(cotar.view.reads(), 3);
+
+    let merged = cotar.get_many(&paths, 1024).unwrap();
+    assert_eq!(cotar.view.reads(), 4);
+    assert_eq!(files, merged);
+
+    for (path, file) in paths.iter().zip(files) {
+        match TEST_FILES.iter().find(|(name, _)| name == path) {
+            Some((_, data)) => assert_eq!(file.unwrap().as_ref(), *data),
+            None => assert!(file.is_none()),
+        }
+    }
+}
+
+#[test]
+fn test_verify_paths() {
+    use crate::test_util::{create_archive, TEST_FILES};
+
+    let long_path = format!("tiles/{}/0/0.pbf", "a".repeat(200));
+    let mut files = TEST_FILES.to_vec();
+    files.push((long_path.as_str(), b"long path"));
+    let archive = create_archive("verify_paths", &files);
+
+    let mut cotar = Cotar::from_tar(archive.embedded()).unwrap();
+    cotar.set_verify_paths(true);
+    for (path, data) in files {
+        assert_eq!(cotar.get(path).unwrap().unwrap().as_ref(), data);
+    }
+    assert!(cotar.get("tiles/2/0/0.pbf").unwrap().is_none());
+}
+
+#[test]
+fn test_verify_paths_hard_links() {
+    use crate::test_util::{create_archive_with_links, TEST_FILES};
+
+    let links = [
+        ("tiles/2/0/0.pbf", "tiles/0/0/0.pbf"),
+        ("tiles/2/1/0.pbf", "tiles/0/0/0.pbf"),
+    ];
+    let archive = create_archive_with_links("verify_paths_hard_links", &TEST_FILES, &links);
+
+    let mut cotar = Cotar::from_tar(archive.embedded()).unwrap();
+    cotar.set_verify_paths(true);
+    for (path, target) in links {
+        assert_eq!(cotar.get(path).unwrap(), cotar.get(target).unwrap());
+    }
+    assert!(cotar.get("tiles/2/2/0.pbf").unwrap().is_none());
+}
+
+#[test]
+fn test_verify_paths_large_extension() {
+    use crate::test_util::create_archive;
+
+    // GNU long names larger than `TAR_EXTENSION_LOOKBACK` and `MAX_EXTENSION_LOOKBACK`
+    let long_path = format!("tiles/{}/0/0.pbf", "a".repeat(10_000));
+    let huge_path = format!("tiles/{}/0/0.pbf", "b".repeat(600_000));
+    let files = [
+        ("tiles/0/0/0.pbf", &b"tile 0/0/0"[..]),
+        (long_path.as_str(), b"long"),
+        (huge_path.as_str(), b"huge"),
+    ];
+    let archive = create_archive("verify_paths_large_extension", &files);
+
+    let mut cotar = Cotar::from_tar(archive.embedded()).unwrap();
+    cotar.set_verify_paths(true);
+    assert_eq!(cotar.get(&long_path).unwrap().unwrap().as_ref(), b"long");
+    let err = cotar.get(&huge_path).unwrap_err();
+    assert!(matches!(err, crate::CotarError::UnverifiedPath(path) if path == huge_path));
+}
+
+#[test]
+fn test_verify_paths_collision() {
+    use crate::test_util::{create_archive, TEST_FILES};
+
+    let archive = create_archive("verify_paths_collision", &TEST_FILES);
+    let mut index = crate::CotarIndex::new();
+    let mut offset = 0;
+    for (path, data) in TEST_FILES {
+        offset += 512;
+        // Store every file under the wrong path to simulate hash collisions
+        index
+            .add(&format!("{}.wrong", path), offset, data.len() as u64)
+            .unwrap();
+        offset += (data.len() as u64).div_ceil(512) * 512;
+    }
+    let mut tar = std::fs::read(&archive.tar).unwrap();
+    tar.extend_from_slice(&index.pack(1.25).unwrap().vec);
+
+    let mut cotar = Cotar::from_source(Bytes::from(tar)).unwrap();
+    assert!(cotar.get("tiles/0/0/0.pbf.wrong").unwrap().is_some());
+
+    cotar.set_verify_paths(true);
+    let err = cotar.get("tiles/0/0/0.pbf.wrong").unwrap_err();
+    assert!(
+        matches!(err, crate::CotarError::HashCollision(path) if path == "tiles/0/0/0.pbf.wrong")
+    );
+    assert!(cotar.get("tiles/2/0/0.pbf").unwrap().is_none());
+}
+
+#[test]
+fn test_v1_index() {
+    use crate::test_util::{create_archive, TEST_FILES};
+
+    let archive = create_archive("v1_index", &TEST_FILES);
+    let index = crate::CotarIndex::from_tar(archive.tar(), 0).unwrap();
+
+    // Pack a v1 index, hash, file offset and file size as u64 with linear probing
+    let slots = index.entries.len() as u64 * 2;
+    let mut buf = vec![0; (16 + slots * COTAR_V1_INDEX_ENTRY_SIZE) as usize];
+    let mut header = COTAR_V1_HEADER_MAGIC.to_le_bytes().to_vec();
+    header.extend_from_slice(&(slots as u32).to_le_bytes());
+    buf[..8].copy_from_slice(&header);
+    let footer = buf.len() - 8;
+    buf[footer..].copy_from_slice(&header);
+    for entry in index.entries.values() {
+        let mut slot = entry.hash % slots;
+        let offset = loop {
+            let offset = (8 + slot * COTAR_V1_INDEX_ENTRY_SIZE) as usize;
+            if buf[offset..offset + 8] == [0; 8] {
+                break offset;
+            }
+            slot = (slot + 1) % slots;
+        };
+        buf[offset..offset + 8].copy_from_slice(&entry.hash.to_le_bytes());
+        buf[offset + 8..offset + 16].copy_from_slice(&entry.file_offset.to_le_bytes());
+        buf[offset + 16..offset + 24].copy_from_slice(&entry.file_size.to_le_bytes());
+    }
+
+    let tar = Bytes::from(std::fs::read(&archive.tar).unwrap());
+    let mut embedded = tar.to_vec();
+    embedded.extend_from_slice(&buf);
+
+    for cotar in [
+        Cotar::from_sources(tar, Bytes::from(buf)).unwrap(),
+        Cotar::from_source(Bytes::from(embedded)).unwrap(),
+    ] {
+        assert_eq!(cotar.version, 1);
+        crate::test_util::assert_test_files(&cotar);
+    }
+}
+
+#[test]
+fn test_truncated_archive() {
+    use crate::test_util::{create_archive, TEST_FILES};
+
+    for bytes in [&b""[..], b"COT\x02", b"COT\x02\xff\xff\xff\xff"] {
+        let err = Cotar::from_source(Bytes::from_static(bytes)).unwrap_err();
+        assert!(matches!(err, crate::CotarError::TruncatedIndex));
+        let err = Cotar::from_sources(Bytes::new(), Bytes::from_static(bytes)).unwrap_err();
+        assert!(matches!(err, crate::CotarError::TruncatedIndex));
+    }
+
+    // Every truncation of a archive must fail cleanly rather than panic
+    let archive = create_archive("truncated_archive", &TEST_FILES);
+    let embedded = Bytes::from(std::fs::read(&archive.embedded).unwrap());
+    for len in 0..embedded.len() {
+        let Ok(mut cotar) = Cotar::from_source(embedded.slice(..len)) else {
+            continue;
+        };
+        cotar.set_verify_paths(true);
+        for (path, _) in TEST_FILES {
+            let _ = cotar.get(path);
+        }
+        assert!(cotar.load_index().is_err());
+    }
+}
+
+#[test]
+fn test_out_of_bounds_entry() {
+    use crate::test_util::{create_archive, TEST_FILES};
+
+    let archive = create_archive("out_of_bounds_entry", &TEST_FILES);
+    let tar = Bytes::from(std::fs::read(&archive.tar).unwrap());
+
+    let mut index = crate::CotarIndex::new();
+    index.add("inside", 512, 4).unwrap();
+    index.add("outside", tar.len() as u64, 1).unwrap();
+    index.add("overlaps", 512, tar.len() as u64).unwrap();
+    let index = Bytes::from(index.pack(2.0).unwrap().vec);
+
+    let mut cotar = Cotar::from_sources(tar.clone(), index.clone()).unwrap();
+    assert!(cotar.get("inside").unwrap().is_some());
+    for path in ["outside", "overlaps"] {
+        let err = cotar.get(path).unwrap_err();
+        assert!(matches!(err, crate::CotarError::OutOfBounds { .. }));
+    }
+    // Iteration stops at the first entry out of bounds
+    let results: Vec<_> = cotar.iter().collect();
+    assert!(matches!(
+        results.last(),
+        Some(Err(crate::CotarError::OutOfBounds { .. }))
+    ));
+    assert_eq!(results.iter().filter(|result| result.is_err()).count(), 1);
+
+    cotar.set_max_file_size(2);
+    let err = cotar.get("inside").unwrap_err();
+    assert!(matches!(
+        err,
+        crate::CotarError::FileTooLarge { file_size: 4, .. }
+    ));
+    let err = cotar.get_many(&["inside"], 0).unwrap_err();
+    assert!(matches!(err, crate::CotarError::FileTooLarge { .. }));
+
+    // Files of a embedded index cannot overlap the index
+    let mut embedded = tar.to_vec();
+    embedded.extend_from_slice(&index);
+    let embedded = Bytes::from(embedded);
+    let cotar = Cotar::from_source(embedded.clone()).unwrap();
+    let err = cotar.get("outside").unwrap_err();
+    assert!(matches!(err, crate::CotarError::OutOfBounds { .. }));
+
+    // Index header must match the footer
+    let mut corrupt = embedded.to_vec();
+    let header = cotar.index_offset as usize;
+    corrupt[header + 4] ^= 0xff;
+    let mut cotar = Cotar::from_source(Bytes::from(corrupt)).unwrap();
+    assert!(matches!(
+        cotar.load_index().unwrap_err(),
+        crate::CotarError::TruncatedIndex
+    ));
+}
+
+#[test]
+fn test_empty_index() {
+    let index = crate::CotarIndex::new().pack(1.0).unwrap().vec;
+    let cotar = Cotar::from_source(Bytes::from(index)).unwrap();
+    assert_eq!(cotar.entries, 0);
+    assert!(cotar.get("tiles/0/0/0.pbf").unwrap().is_none());
+}
+
+#[test]
+fn test_v3_index() {
+    use crate::test_util::{create_archive, TEST_FILES};
+
+    let archive = create_archive("v3_index", &TEST_FILES);
+    let mut index = crate::CotarIndex::from_tar(archive.tar(), 0).unwrap();
+    index.set_version(3);
+    let packed = index.pack(1.25).unwrap().vec;
+    assert_eq!(
+        packed.len() as u64,
+        COTAR_V3_HEADER_SIZE * 2 + 5 * COTAR_V3_INDEX_ENTRY_SIZE
+    );
+
+    let tar = Bytes::from(std::fs::read(&archive.tar).unwrap());
+    let mut embedded = tar.to_vec();
+    embedded.extend_from_slice(&packed);
+
+    for mut cotar in [
+        Cotar::from_sources(tar, Bytes::from(packed)).unwrap(),
+        Cotar::from_source(Bytes::from(embedded)).unwrap(),
+    ] {
+        assert_eq!(cotar.version, 3);
+        cotar.load_index().unwrap();
+        cotar.set_verify_paths(true);
+        crate::test_util::assert_test_files(&cotar);
+    }
+}
+
+#[test]
+fn test_v3_large_entries() {
+    /// Source of zeros that is larger than any real file
+    struct Zeros;
+    impl Source for Zeros {
+        fn size(&self) -> u64 {
+            1 << 50
+        }
+        fn read_exact(&self, _offset: u64, len: u64) -> std::io::Result<Bytes> {
+            Ok(Bytes::from(vec![0; len as usize]))
+        }
+    }
+
+    // Past the 2TiB block offset and 4GiB file size limits of v2
+    let file_offset = 3 << 40;
+    let file_size = 5 << 30;
+    let mut index = crate::CotarIndex::new();
+    index.add("large.tiff", file_offset, file_size).unwrap();
+    index.set_version(3);
+
+    let packed = Bytes::from(index.pack(1.0).unwrap().vec);
+    let view: Box<dyn Source> = Box::new(Zeros);
+    let cotar = Cotar::from_sources(view, Box::new(packed)).unwrap();
+    let entry = cotar.info("large.tiff").unwrap().unwrap();
+    assert_eq!(entry.file_offset, file_offset);
+    assert_eq!(entry.file_size, file_size);
+
+    // v2 cannot store the entry so it fails rather than truncating
+    index.set_version(2);
+    let err = index.pack(1.0).unwrap_err();
+    assert!(matches!(
+        err,
+        crate::CotarError::EntryOverflow { version: 2, .. }
+    ));
+}
+
+#[test]
+fn test_hash_algorithm_index() {
+    use crate::test_util::{create_archive, TEST_FILES};
+
+    let archive = create_archive("hash_algorithm_index", &TEST_FILES);
+    let tar = Bytes::from(std::fs::read(&archive.tar).unwrap());
+
+    for hash_algorithm in [
+        HashAlgorithm::Fnv1a,
+        HashAlgorithm::Xxh3,
+        HashAlgorithm::SipHash,
+    ] {
+        let mut index = crate::CotarIndex::with_hash_algorithm(hash_algorithm);
+        index.add_tar(archive.tar(), 0).unwrap();
+        let packed = index.pack(1.25).unwrap().vec;
+        let mut embedded = tar.to_vec();
+        embedded.extend_from_slice(&packed);
+
+        for mut cotar in [
+            Cotar::from_sources(tar.clone(), Bytes::from(packed.clone())).unwrap(),
+            Cotar::from_source(Bytes::from(embedded)).unwrap(),
+        ] {
+            assert_eq!(cotar.hash_algorithm, hash_algorithm);
+            cotar.load_index().unwrap();
+            crate::test_util::assert_test_files(&cotar);
+        }
+    }
+
+    // Only v3 indexes can store the hash algorithm
+    let mut index = crate::CotarIndex::with_hash_algorithm(HashAlgorithm::Xxh3);
+    assert_eq!(index.version(), 3);
+    index.set_version(2);
+    let err = index.pack(1.0).unwrap_err();
+    assert!(matches!(
+        err,
+        crate::CotarError::UnsupportedHashAlgorithm(1)
+    ));
+
+    // Unknown hash algorithms fail to open
+    index.set_version(3);
+    let mut packed = index.pack(1.0).unwrap().vec;
+    packed[8] = 0xff;
+    let err = Cotar::from_sources(Bytes::new(), Bytes::from(packed)).unwrap_err();
+    assert!(matches!(
+        err,
+        crate::CotarError::UnsupportedHashAlgorithm(0xff)
+    ));
+}
+
+#[test]
+fn test_path_table() {
+    use crate::test_util::{create_archive, TEST_FILES};
+
+    let archive = create_archive("path_table", &TEST_FILES);
+    let tar = Bytes::from(std::fs::read(&archive.tar).unwrap());
+
+    let mut index = crate::CotarIndex::new();
+    index.set_path_table(true);
+    index.add_tar(archive.tar(), 0).unwrap();
+    let packed = index.pack(1.25).unwrap();
+    let table = packed.path_table.unwrap();
+
+    // Embedded tables are written before the index, separate tables after the index
+    let mut embedded = tar.to_vec();
+    embedded.extend_from_slice(&table);
+    embedded.extend_from_slice(&packed.vec);
+    let mut separate = packed.vec.clone();
+    separate.extend_from_slice(&table);
+
+    for mut cotar in [
+        Cotar::from_source(Bytes::from(embedded)).unwrap(),
+        Cotar::from_sources(tar.clone(), Bytes::from(separate)).unwrap(),
+    ] {
+        // Readers that do not load the table still work
+        crate::test_util::assert_test_files(&cotar);
+
+        assert!(cotar.load_path_table().unwrap());
+        let table = cotar.path_table().unwrap();
+        assert_eq!(table.len(), TEST_FILES.len());
+        let mut tiles: Vec<_> = table
+            .with_prefix("tiles/1/")
+            .map(|(_, path)| path)
+            .collect();
+        tiles.sort();
+        assert_eq!(tiles, vec!["tiles/1/0/0.pbf", "tiles/1/1/0.pbf"]);
+
+        crate::test_util::assert_test_files(&cotar);
+    }
+
+    // Archives without a table
+    let mut cotar = Cotar::from_tar(archive.embedded()).unwrap();
+    assert!(!cotar.load_path_table().unwrap());
+    assert!(cotar.path_table().is_none());
+    let mut cotar = Cotar::from_tar_index(archive.tar(), archive.index()).unwrap();
+    assert!(!cotar.load_path_table().unwrap());
+}
+
+#[test]
+fn test_path_table_bounds() {
+    use crate::test_util::{create_archive, TEST_FILES};
+
+    let archive = create_archive("path_table_bounds", &TEST_FILES);
+    let tar = std::fs::read(&archive.tar).unwrap();
+
+    // Entry pointing at the path table that is written after the tar
+    let mut index = crate::CotarIndex::new();
+    index.set_path_table(true);
+    index.add_tar(archive.tar(), 0).unwrap();
+    index.add("table.bin", tar.len() as u64, 16).unwrap();
+    let packed = index.pack(1.25).unwrap();
+    let mut embedded = tar.clone();
+    embedded.extend_from_slice(&packed.path_table.unwrap());
+    embedded.extend_from_slice(&packed.vec);
+    let embedded = Bytes::from(embedded);
+
+    // The tail covers the footer of the table so it is found on open
+    let cotar = Cotar::from_source_with_tail(embedded.clone(), DEFAULT_TAIL_SIZE).unwrap();
+    let err = cotar.get("table.bin").unwrap_err();
+    assert!(matches!(err, crate::CotarError::OutOfBounds { .. }));
+    assert!(cotar.read_path_table().unwrap().is_some());
+
+    // Otherwise it is found when the table is loaded
+    let mut cotar = Cotar::from_source(embedded).unwrap();
+    assert!(cotar.read_path_table().unwrap().is_some());
+    assert!(cotar.load_path_table().unwrap());
+    let err = cotar.get("table.bin").unwrap_err();
+    assert!(matches!(err, crate::CotarError::OutOfBounds { .. }));
+}
+
+#[test]
+fn test_path_table_collision() {
+    use crate::test_util::{create_archive, TEST_FILES};
+
+    let archive = create_archive("path_table_collision", &TEST_FILES);
+    let mut index = crate::CotarIndex::new();
+    index.add_tar(archive.tar(), 0).unwrap();
+    let packed = index.pack(1.25).unwrap();
+
+    // Table naming every slot with another path, as if every lookup hit a hash collision
+    let cotar = Cotar::from_sources(Bytes::new(), Bytes::from(packed.vec.clone())).unwrap();
+    let slot_paths: Vec<Option<&str>> = (0..cotar.entries)
+        .map(|slot| {
+            let offset =
+                (slot * index_entry_size(cotar.version) + header_size(cotar.version)) as usize;
+            match packed.vec[offset..offset + 8] == [0; 8] {
+                true => None,
+                false => Some("other.pbf"),
+            }
+        })
+        .collect();
+    let mut separate = packed.vec.clone();
+    separate.extend_from_slice(&PathTable::pack(&slot_paths));
+
+    let tar = Bytes::from(std::fs::read(&archive.tar).unwrap());
+    let mut cotar = Cotar::from_sources(tar, Bytes::from(separate)).unwrap();
+    cotar.set_verify_paths(true);
+    assert!(cotar.get(TEST_FILES[0].0).unwrap().is_some());
+    assert!(cotar.load_path_table().unwrap());
+    assert!(cotar.get(TEST_FILES[0].0).unwrap().is_none());
+
+    // Tables that do not match the index are rejected
+    let mut separate = packed.vec.clone();
+    separate.extend_from_slice(&PathTable::pack(&[Some("other.pbf")]));
+    let mut cotar = Cotar::from_sources(Bytes::new(), Bytes::from(separate)).unwrap();
+    let err = cotar.load_path_table().unwrap_err();
+    assert!(matches!(err, crate::CotarError::InvalidPathTable));
+}
+
+#[test]
+fn test_iter_entries() {
+    use crate::test_util::{create_archive, TEST_FILES};
+
+    let long_path = format!("tiles/{}/0/0.pbf", "a".repeat(200));
+    let mut files = TEST_FILES.to_vec();
+    files.push((long_path.as_str(), b"long path"));
+    let archive = create_archive("iter_entries", &files);
+    let mut expected: Vec<&str> = files.iter().map(|(path, _)| *path).collect();
+    expected.sort();
+
+    for mut cotar in [
+        Cotar::from_tar(archive.embedded()).unwrap(),
+        Cotar::from_tar_index(archive.tar(), archive.index()).unwrap(),
+    ] {
+        for _ in 0..2 {
+            let entries: Vec<_> = cotar.iter().collect::<CotarResult<_>>().unwrap();
+            assert_eq!(entries.len(), files.len());
+
+            let mut paths = Vec::new();
+            for item in cotar.iter().with_paths() {
+                let (entry, path) = item.unwrap();
+                let path = path.unwrap();
+                assert_eq!(cotar.info(&path).unwrap(), Some(entry));
+                paths.push(path);
+            }
+            paths.sort();
+            assert_eq!(paths, expected);
+
+            cotar.load_index().unwrap();
+        }
+        assert_eq!(cotar.entry_at(cotar.entries).unwrap(), None);
+    }
+}
+
+#[test]
+fn test_iter_entries_path_table() {
+    use crate::test_util::{create_archive, TEST_FILES};
+
+    let archive = create_archive("iter_entries_path_table", &TEST_FILES);
+    let mut index = crate::CotarIndex::new();
+    index.set_path_table(true);
+    // Entries pointing at the wrong tar header can only be named by the path table
+    let mut offset = 0;
+    for (path, data) in TEST_FILES {
+        offset += 512;
+        index.add(path, offset + 512, data.len() as u64).unwrap();
+        offset += (data.len() as u64).div_ceil(512) * 512;
+    }
+    let packed = index.pack(1.25).unwrap();
+    let mut separate = packed.vec.clone();
+    separate.extend_from_slice(&packed.path_table.unwrap());
+
+    let tar = Bytes::from(std::fs::read(&archive.tar).unwrap());
+    let mut cotar = Cotar::from_sources(tar, Bytes::from(separate)).unwrap();
+    assert!(cotar
+        .iter()
+        .with_paths()
+        .all(|item| item.unwrap().1.is_none()));
+
+    cotar.load_path_table().unwrap();
+    let mut paths: Vec<String> = cotar
+        .iter()
+        .with_paths()
+        .map(|item| item.unwrap().1.unwrap())
+        .collect();
+    paths.sort();
+    let mut expected: Vec<_> = TEST_FILES.iter().map(|(path, _)| *path).collect();
+    expected.sort();
+    assert_eq!(paths, expected);
 }
@@ -1,21 +1,34 @@
 use std::cmp::Ordering;
 use std::collections::HashMap;
 use std::fs::File;
-use std::io::Error;
-use std::io::ErrorKind;
 use std::io::Read;
-use std::io::Result as IoResult;
 use std::io::Write;
 use tar::Archive;
 use tar::EntryType;
+use tracing::{debug, info, instrument, trace};
+
+use crate::error::{CotarError, CotarResult};
+use crate::path_table::PathTable;
+use cotar_core::{header_magic, header_size, index_entry_size, HashAlgorithm};
 
 pub struct CotarIndex {
     pub entries: HashMap<u64, crate::CotarIndexEntry>,
+    /// Index version to pack
+    version: u8,
+    /// Algorithm used to hash file paths
+    hash_algorithm: HashAlgorithm,
+    /// Paths of the entries when a path table is being created
+    paths: Option<HashMap<u64, String>>,
 }
 
+#[derive(Debug)]
 pub struct CotarIndexResult {
     /// Packed buffer
     pub vec: Vec<u8>,
+    /// Packed path table if enabled with `set_path_table`
+    ///
+    /// Written directly before a index that is embedded in a tar, or directly after a separate index
+    pub path_table: Option<Vec<u8>>,
     /// Total entries packed
     pub entries: usize,
     /// Max number of records to search to find a record
@@ -32,59 +45,110 @@ impl Default for CotarIndex {
 
 impl CotarIndex {
     pub fn new() -> Self {
+        CotarIndex::with_hash_algorithm(HashAlgorithm::default())
+    }
+
+    /// Create a empty CotarIndex that hashes paths with `hash_algorithm`
+    ///
+    /// Only v3 indexes can store the hash algorithm, so algorithms other than fnv1a pack a v3 index
+    pub fn with_hash_algorithm(hash_algorithm: HashAlgorithm) -> Self {
         CotarIndex {
             entries: HashMap::new(),
+            version: match hash_algorithm {
+                HashAlgorithm::Fnv1a => 2,
+                _ => 3,
+            },
+            hash_algorithm,
+            paths: None,
+        }
+    }
+
+    /// Record the path of every file added to pack a path table next to the index
+    ///
+    /// Must be enabled before any files are added
+    pub fn set_path_table(&mut self, enabled: bool) {
+        match enabled {
+            true => {
+                self.paths.get_or_insert_with(HashMap::new);
+            }
+            false => self.paths = None,
         }
     }
 
+    /// Algorithm used to hash file paths
+    pub fn hash_algorithm(&self) -> HashAlgorithm {
+        self.hash_algorithm
+    }
+
+    /// Index version that `pack` writes
+    pub fn version(&self) -> u8 {
+        self.version
+    }
+
+    /// Set the index version that `pack` writes, defaults to v2
+    ///
+    /// v2 indexes are the smallest but are limited to tars of 2TiB and files of 4GiB,
+    /// v3 indexes store 64 bit offsets and sizes
+    pub fn set_version(&mut self, version: u8) {
+        self.version = version;
+    }
+
     /// Create a CotarIndex from a tar file
-    pub fn from_tar(file_name: &str, report_at: usize) -> IoResult<CotarIndex> {
+    pub fn from_tar(file_name: &str, report_at: usize) -> CotarResult<CotarIndex> {
+        let mut cotar_index = CotarIndex::new();
+        cotar_index.add_tar(file_name, report_at)?;
+        Ok(cotar_index)
+    }
+
+    /// Add all the files from a tar file
+    ///
+    /// Progress is reported as a `tracing` event every `report_at` files, 0 disables reporting
+    #[instrument(level = "info", skip(self))]
+    pub fn add_tar(&mut self, file_name: &str, report_at: usize) -> CotarResult<()> {
         let file = File::open(file_name)?;
         let mut a = Archive::new(file);
 
-        let mut cotar_index = CotarIndex::new();
         for file in a.entries()? {
             let file = file?;
 
-            let header = file.header();
-            let file_path = header.path()?;
-            let file_name = file_path.to_str().expect("Failed to extract file_name");
+            // Entry paths include any GNU long name or PAX path extensions
+            let file_path = file.path()?.into_owned();
+            let file_name = file_path.to_str().ok_or_else(|| invalid_path(&file_path))?;
 
+            let header = file.header();
             match header.entry_type() {
                 EntryType::Regular => {
                     // offset to the file is at end of the header
                     let file_offset = file.raw_header_position() + 512;
-                    // println!("load_entry {} {:?} {:?}", file_offset, header, header.entry_type());
-                    let file_size = header.size()? as u32;
-                    cotar_index.add(file_name, file_offset, file_size)?;
+                    let file_size = header.size()?;
+                    trace!(file_name, file_offset, file_size, "Add file");
+                    self.add(file_name, file_offset, file_size)?;
                 }
                 EntryType::Link => {
-                    let link_path = header.link_name()?.expect("No link_path found?");
-                    let link_name = link_path.to_str().expect("Failed to extract link_path");
-                    cotar_index.link(file_name, link_name)?;
+                    let link_path = file.link_name()?.unwrap_or_default();
+                    let link_name = link_path.to_str().ok_or_else(|| invalid_path(&link_path))?;
+                    self.link(file_name, link_name)?;
                 }
-                _e => {
+                e => {
                     // TODO what to do with these types
                     // Folders/other files, ignore for now??
-                    // println!("Unknown entry_type: {:?}", e)
+                    trace!(file_name, entry_type = ?e, "Skip entry");
                 }
             }
 
             // If a report is requested dump how far through the file we are.
-            if report_at > 0 && cotar_index.entries.len() % report_at == 0 {
-                println!("{}", cotar_index.entries.len());
+            if report_at > 0 && self.entries.len() % report_at == 0 {
+                info!(files = self.entries.len(), "Indexing tar");
             }
         }
-        Ok(cotar_index)
+        debug!(files = self.entries.len(), "Tar indexed");
+        Ok(())
     }
 
-    pub fn add(&mut self, path: &str, file_offset: u64, file_size: u32) -> IoResult<()> {
-        let hash = crate::Cotar::hash(path);
+    pub fn add(&mut self, path: &str, file_offset: u64, file_size: u64) -> CotarResult<()> {
+        let hash = self.hash_algorithm.hash(path.as_bytes());
         if self.entries.contains_key(&hash) {
-            return Err(Error::new(
-                ErrorKind::Other,
-                format!("Duplicate hash key : {}", path),
-            ));
+            return Err(CotarError::DuplicatePath(path.to_string()));
         }
 
         let entry = crate::CotarIndexEntry {
@@ -93,17 +157,23 @@ impl CotarIndex {
             file_size,
         };
         self.entries.insert(entry.hash, entry);
+        if let Some(paths) = &mut self.paths {
+            paths.insert(hash, path.to_string());
+        }
         Ok(())
     }
 
     /// If a file is the exact same as another file in the archive, create a link
     /// rather than storing the file twice
-    pub fn link(&mut self, source: &str, target: &str) -> IoResult<()> {
-        let hash_target = crate::Cotar::hash(target);
+    pub fn link(&mut self, source: &str, target: &str) -> CotarResult<()> {
+        let hash_target = self.hash_algorithm.hash(target.as_bytes());
         let entry = self.entries.get(&hash_target);
 
         match entry {
-            None => Err(Error::new(ErrorKind::Other, "Missing link target")),
+            None => Err(CotarError::MissingLinkTarget {
+                path: source.to_string(),
+                target: target.to_string(),
+            }),
             Some(e) => {
                 let file_size = e.file_size;
                 let file_offset = e.file_offset;
@@ -113,26 +183,34 @@ impl CotarIndex {
     }
 
     /// Pack the COTAR index into a buffer with the specified amount of excess slots
-    pub fn pack(&mut self, packing_factor: f64) -> IoResult<CotarIndexResult> {
+    ///
+    /// Fails with `EntryOverflow` if a entry does not fit in the index version
+    pub fn pack(&mut self, packing_factor: f64) -> CotarResult<CotarIndexResult> {
+        let version = self.version;
+        if version != 2 && version != 3 {
+            return Err(CotarError::UnsupportedVersion(version));
+        }
+        // v2 indexes do not have space to store the hash algorithm
+        if version == 2 && self.hash_algorithm != HashAlgorithm::Fnv1a {
+            return Err(CotarError::UnsupportedHashAlgorithm(
+                self.hash_algorithm.id(),
+            ));
+        }
+        let header_size = header_size(version);
+        let entry_size = index_entry_size(version);
+
         let entry_count = self.entries.len();
         // Cannot pack into less than 100% size...
         if packing_factor < 1.0 {
-            return Err(std::io::Error::new(
-                ErrorKind::Other,
-                "Packing factor too low",
-            ));
+            return Err(CotarError::PackingFactorTooLow(packing_factor));
         }
         // Slot count is limited to uint32
         let slot_count = ((entry_count as f64) * packing_factor).floor() as u64;
         if slot_count >= (u32::MAX as u64) {
-            return Err(std::io::Error::new(
-                ErrorKind::Other,
-                "Too many index entries",
-            ));
+            return Err(CotarError::TooManyEntries(slot_count));
         }
 
-        let buffer_size =
-            crate::COTAR_V2_HEADER_SIZE * 2 + crate::COTAR_V2_INDEX_ENTRY_SIZE * slot_count;
+        let buffer_size = header_size * 2 + entry_size * slot_count;
 
         let mut all_values: Vec<&crate::CotarIndexEntry> = self.entries.values().collect();
         // Sort the entries into the order they should be written to the file
@@ -152,20 +230,31 @@ impl CotarIndex {
 
         let output: Vec<u8> = Vec::with_capacity(buffer_size as usize);
         let mut cursor = std::io::Cursor::new(output);
+        // v3 index options, the hash algorithm followed by reserved bytes
+        let mut options = vec![0; (header_size - crate::COTAR_V2_HEADER_SIZE) as usize];
+        if let Some(hash_algorithm) = options.first_mut() {
+            *hash_algorithm = self.hash_algorithm.id();
+        }
+
         // Write the header
-        cursor.write_all(&u32::to_le_bytes(crate::COTAR_V2_HEADER_MAGIC))?;
+        cursor.write_all(&u32::to_le_bytes(header_magic(version)))?;
         cursor.write_all(&u32::to_le_bytes(slot_count as u32))?;
+        cursor.write_all(&options)?;
 
-        // Write the footer
-        cursor.set_position(buffer_size - crate::COTAR_V2_HEADER_SIZE);
-        cursor.write_all(&u32::to_le_bytes(crate::COTAR_V2_HEADER_MAGIC))?;
+        // Write the footer, ending with the magic and slot count so it can be read from the end of the file
+        cursor.set_position(buffer_size - header_size);
+        cursor.write_all(&options)?;
+        cursor.write_all(&u32::to_le_bytes(header_magic(version)))?;
         cursor.write_all(&u32::to_le_bytes(slot_count as u32))?;
 
+        let mut slot_paths: Option<Vec<Option<&str>>> =
+            self.paths.as_ref().map(|_| vec![None; slot_count as usize]);
+
         let mut max_search_count: usize = 0;
         let mut total_search_count: usize = 0;
         for entry in all_values {
             let mut search_count: usize = 0;
-            let mut index = (entry.hash % slot_count) as u64;
+            let mut index = entry.hash % slot_count;
             let start_index = index;
 
             loop {
@@ -173,7 +262,7 @@ impl CotarIndex {
                 if index >= slot_count {
                     index = 0;
                 }
-                let offset = crate::COTAR_V2_INDEX_ENTRY_SIZE * index + crate::COTAR_V2_HEADER_SIZE;
+                let offset = entry_size * index + header_size;
 
                 let mut hash_buf = [0; 8];
                 cursor.set_position(offset);
@@ -190,7 +279,7 @@ impl CotarIndex {
                 index += 1;
                 // If the index loops all the way around to the start something horrible has happened
                 if index == start_index {
-                    return Err(std::io::Error::new(ErrorKind::Other, "Hash index looped"));
+                    return Err(CotarError::IndexFull);
                 }
             }
 
@@ -199,18 +288,93 @@ impl CotarIndex {
             }
             total_search_count += search_count;
 
-            // Tar files are aligned to 512 byte blocks store the block offset not the file offset
-            let file_block_offset = (entry.file_offset / 512) as u32;
+            if let (Some(paths), Some(slot_paths)) = (&self.paths, &mut slot_paths) {
+                let path = paths
+                    .get(&entry.hash)
+                    .ok_or(CotarError::MissingPath(entry.hash))?;
+                slot_paths[index as usize] = Some(path);
+            }
+
             cursor.write_all(&u64::to_le_bytes(entry.hash))?;
-            cursor.write_all(&u32::to_le_bytes(file_block_offset))?;
-            cursor.write_all(&u32::to_le_bytes(entry.file_size))?;
+            match version {
+                2 => {
+                    // Tar files are aligned to 512 byte blocks store the block offset not the file offset
+                    let overflow = CotarError::EntryOverflow {
+                        version,
+                        file_offset: entry.file_offset,
+                        file_size: entry.file_size,
+                    };
+                    if entry.file_offset % 512 != 0 {
+                        return Err(overflow);
+                    }
+                    let file_block_offset = u32::try_from(entry.file_offset / 512);
+                    let file_size = u32::try_from(entry.file_size);
+                    let (Ok(file_block_offset), Ok(file_size)) = (file_block_offset, file_size)
+                    else {
+                        return Err(overflow);
+                    };
+                    cursor.write_all(&u32::to_le_bytes(file_block_offset))?;
+                    cursor.write_all(&u32::to_le_bytes(file_size))?;
+                }
+                _ => {
+                    cursor.write_all(&u64::to_le_bytes(entry.file_offset))?;
+                    cursor.write_all(&u64::to_le_bytes(entry.file_size))?;
+                }
+            }
         }
 
+        debug!(
+            packing_factor,
+            slots = slot_count,
+            search_max = max_search_count,
+            "Index packed"
+        );
         Ok(CotarIndexResult {
             vec: cursor.into_inner(),
+            path_table: slot_paths.map(|slot_paths| PathTable::pack(&slot_paths)),
             entries: entry_count,
             search_max: max_search_count,
             search_avg: (total_search_count as f64) / (entry_count as f64),
         })
     }
 }
+
+/// Paths are hashed as UTF-8 so paths that are not valid UTF-8 cannot be indexed
+fn invalid_path(path: &std::path::Path) -> CotarError {
+    CotarError::Io(std::io::Error::new(
+        std::io::ErrorKind::InvalidData,
+        format!("Path is not valid UTF-8: {}", path.display()),
+    ))
+}
+
+#[test]
+fn test_pack_v2_overflow() {
+    for (file_offset, file_size) in [
+        // Not aligned to a 512 byte block
+        (100, 1),
+        // Block offset larger than a u32
+        ((u32::MAX as u64 + 1) * 512, 1),
+        // File size larger than a u32
+        (512, u32::MAX as u64 + 1),
+    ] {
+        let mut index = CotarIndex::new();
+        index
+            .add("tiles/0/0/0.pbf", file_offset, file_size)
+            .unwrap();
+        let err = index.pack(1.0).unwrap_err();
+        assert!(matches!(err, CotarError::EntryOverflow { version: 2, .. }));
+
+        index.set_version(3);
+        assert!(index.pack(1.0).is_ok());
+    }
+
+    let mut index = CotarIndex::new();
+    index
+        .add("tiles/0/0/0.pbf", (u32::MAX as u64) * 512, u32::MAX as u64)
+        .unwrap();
+    assert!(index.pack(1.0).is_ok());
+
+    index.set_version(1);
+    let err = index.pack(1.0).unwrap_err();
+    assert!(matches!(err, CotarError::UnsupportedVersion(1)));
+}
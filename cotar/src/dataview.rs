@@ -1,38 +1,118 @@
 use std::fs::File;
-use std::io::BufReader;
-use std::io::Read;
 use std::io::Result as IoResult;
-use std::io::Seek;
+use std::sync::Arc;
 
 use bytes::Bytes;
 
-#[derive(Debug)]
+/// Read only view of a file on disk
+///
+/// Reads are positional (pread) so a view can be shared between threads without locking,
+/// cloning a view only clones the underlying file handle reference.
+/// Platforms without positional reads seek and read while holding a lock on the file
+#[derive(Debug, Clone)]
 pub struct DataView {
-    file: BufReader<std::fs::File>,
+    backing: Backing,
     pub size: u64,
 }
 
+/// File handle that reads can be made from concurrently
+#[cfg(any(unix, windows))]
+type SharedFile = File;
+#[cfg(not(any(unix, windows)))]
+type SharedFile = std::sync::Mutex<File>;
+
+#[derive(Debug, Clone)]
+enum Backing {
+    File(Arc<SharedFile>),
+    /// Memory mapped file, reads are zero-copy slices of the mapping
+    #[cfg(feature = "mmap")]
+    Mmap(Bytes),
+}
+
 impl DataView {
     pub fn open(file_name: &str) -> IoResult<Self> {
         let file = File::open(file_name)?;
         let file_size = file.metadata()?.len();
-        let reader: BufReader<File> = BufReader::new(file);
 
         Ok(DataView {
-            file: reader,
+            backing: Backing::File(Arc::new(SharedFile::from(file))),
             size: file_size,
         })
     }
 
-    pub fn read_exact(&mut self, offset: u64, len: u64) -> IoResult<Bytes> {
-        let mut buf = vec![0; len as usize];
+    /// Open a file as a memory map
+    ///
+    /// The file must not be truncated or modified while it is mapped
+    #[cfg(feature = "mmap")]
+    pub fn open_mmap(file_name: &str) -> IoResult<Self> {
+        let file = File::open(file_name)?;
+        // Safety: cotar archives are treated as immutable once written
+        let mmap = unsafe { memmap2::Mmap::map(&file)? };
+        let file_size = mmap.len() as u64;
+
+        Ok(DataView {
+            backing: Backing::Mmap(Bytes::from_owner(mmap)),
+            size: file_size,
+        })
+    }
+
+    pub fn read_exact(&self, offset: u64, len: u64) -> IoResult<Bytes> {
+        // Check the read is inside the file before allocating a buffer for it
+        if offset.checked_add(len).is_none_or(|end| end > self.size) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::UnexpectedEof,
+                "failed to fill whole buffer",
+            ));
+        }
 
-        let current_position = self.file.stream_position()?;
-        if current_position != offset {
-            let seek_offset: i64 = (offset - current_position) as i64;
-            self.file.seek_relative(seek_offset)?;
+        match &self.backing {
+            Backing::File(file) => {
+                let mut buf = vec![0; len as usize];
+                read_exact_at(file, &mut buf, offset)?;
+                Ok(Bytes::from(buf))
+            }
+            #[cfg(feature = "mmap")]
+            Backing::Mmap(bytes) => Ok(bytes.slice(offset as usize..(offset + len) as usize)),
         }
-        self.file.read_exact(&mut buf)?;
-        Ok(Bytes::from(buf))
     }
 }
+
+#[cfg(unix)]
+fn read_exact_at(file: &File, buf: &mut [u8], offset: u64) -> IoResult<()> {
+    use std::os::unix::fs::FileExt;
+    file.read_exact_at(buf, offset)
+}
+
+#[cfg(windows)]
+fn read_exact_at(file: &File, mut buf: &mut [u8], mut offset: u64) -> IoResult<()> {
+    use std::io::{Error, ErrorKind};
+    use std::os::windows::fs::FileExt;
+
+    while !buf.is_empty() {
+        match file.seek_read(buf, offset) {
+            Ok(0) => {
+                return Err(Error::new(
+                    ErrorKind::UnexpectedEof,
+                    "failed to fill whole buffer",
+                ))
+            }
+            Ok(n) => {
+                buf = &mut buf[n..];
+                offset += n as u64;
+            }
+            Err(e) if e.kind() == ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(any(unix, windows)))]
+fn read_exact_at(file: &std::sync::Mutex<File>, buf: &mut [u8], offset: u64) -> IoResult<()> {
+    use std::io::{Read, Seek, SeekFrom};
+
+    // Every read seeks first, so the position left by a read that panicked does not matter
+    let mut file = file.lock().unwrap_or_else(|e| e.into_inner());
+    file.seek(SeekFrom::Start(offset))?;
+    file.read_exact(buf)
+}
@@ -0,0 +1,204 @@
+use std::fmt;
+#[cfg(any(feature = "gzip", feature = "zstd", feature = "brotli"))]
+use std::io::Read;
+
+use bytes::Bytes;
+
+use crate::error::{CotarError, CotarResult};
+
+/// Compression of a file stored in a archive
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContentEncoding {
+    Gzip,
+    Zstd,
+    Brotli,
+}
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+impl ContentEncoding {
+    /// Detect the compression of a file from its magic bytes or file extension
+    ///
+    /// Gzip and zstd are detected from their magic bytes, brotli has no magic bytes so is
+    /// detected from a `.br` extension
+    pub fn detect(path: &str, bytes: &[u8]) -> Option<Self> {
+        if bytes.starts_with(&GZIP_MAGIC) {
+            return Some(ContentEncoding::Gzip);
+        }
+        if bytes.starts_with(&ZSTD_MAGIC) {
+            return Some(ContentEncoding::Zstd);
+        }
+        match path.ends_with(".br") {
+            true => Some(ContentEncoding::Brotli),
+            false => None,
+        }
+    }
+
+    /// Value of the HTTP `Content-Encoding` header
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ContentEncoding::Gzip => "gzip",
+            ContentEncoding::Zstd => "zstd",
+            ContentEncoding::Brotli => "br",
+        }
+    }
+
+    /// Is decoding supported by the enabled features
+    pub fn is_supported(&self) -> bool {
+        match self {
+            ContentEncoding::Gzip => cfg!(feature = "gzip"),
+            ContentEncoding::Zstd => cfg!(feature = "zstd"),
+            ContentEncoding::Brotli => cfg!(feature = "brotli"),
+        }
+    }
+
+    /// Decode `bytes`, failing with `FileTooLarge` if the decoded file is larger than `max_size`
+    pub fn decode(&self, bytes: &[u8], max_size: u64) -> CotarResult<Bytes> {
+        match self {
+            #[cfg(feature = "gzip")]
+            ContentEncoding::Gzip => {
+                read_limited(*self, flate2::read::GzDecoder::new(bytes), max_size)
+            }
+            #[cfg(feature = "zstd")]
+            ContentEncoding::Zstd => {
+                let decoder = ruzstd::decoding::StreamingDecoder::new(bytes)
+                    .map_err(|e| CotarError::Decode(*self, std::io::Error::other(e)))?;
+                read_limited(*self, decoder, max_size)
+            }
+            #[cfg(feature = "brotli")]
+            ContentEncoding::Brotli => {
+                read_limited(*self, brotli::Decompressor::new(bytes, 4096), max_size)
+            }
+            #[allow(unreachable_patterns)]
+            _ => {
+                let _ = (bytes, max_size);
+                Err(CotarError::UnsupportedEncoding(*self))
+            }
+        }
+    }
+}
+
+impl fmt::Display for ContentEncoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Read a decoder to the end without reading more than `max_size` bytes
+#[cfg(any(feature = "gzip", feature = "zstd", feature = "brotli"))]
+fn read_limited<R: Read>(
+    encoding: ContentEncoding,
+    decoder: R,
+    max_size: u64,
+) -> CotarResult<Bytes> {
+    let mut output = Vec::new();
+    decoder
+        .take(max_size.saturating_add(1))
+        .read_to_end(&mut output)
+        .map_err(|e| CotarError::Decode(encoding, e))?;
+    if output.len() as u64 > max_size {
+        return Err(CotarError::FileTooLarge {
+            file_size: output.len() as u64,
+            max_file_size: max_size,
+        });
+    }
+    Ok(Bytes::from(output))
+}
+
+/// Decode a file if its encoding can be detected, otherwise return it unchanged
+pub(crate) fn decode_file(path: &str, bytes: Bytes, max_size: u64) -> CotarResult<Bytes> {
+    match ContentEncoding::detect(path, &bytes) {
+        Some(encoding) => encoding.decode(&bytes, max_size),
+        None => Ok(bytes),
+    }
+}
+
+#[test]
+fn test_detect() {
+    assert_eq!(
+        ContentEncoding::detect("tiles/0/0/0.pbf", &[0x1f, 0x8b, 0x08]),
+        Some(ContentEncoding::Gzip)
+    );
+    assert_eq!(
+        ContentEncoding::detect("tiles/0/0/0.pbf.zst", &ZSTD_MAGIC),
+        Some(ContentEncoding::Zstd)
+    );
+    assert_eq!(
+        ContentEncoding::detect("tiles/0/0/0.pbf.br", b"data"),
+        Some(ContentEncoding::Brotli)
+    );
+    assert_eq!(ContentEncoding::detect("tiles/0/0/0.pbf.gz", b"data"), None);
+    assert_eq!(ContentEncoding::detect("tiles/0/0/0.pbf", b""), None);
+    assert_eq!(ContentEncoding::Brotli.to_string(), "br");
+}
+
+#[cfg(all(feature = "gzip", feature = "zstd", feature = "brotli"))]
+#[test]
+fn test_decode() {
+    use std::io::Write;
+
+    use crate::test_util::create_archive;
+    use crate::Cotar;
+
+    let data = b"tile 0/0/0 ".repeat(100);
+    let mut gzip = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    gzip.write_all(&data).unwrap();
+    let gzip = gzip.finish().unwrap();
+    let zstd =
+        ruzstd::encoding::compress_to_vec(&data[..], ruzstd::encoding::CompressionLevel::Fastest);
+    let mut brotli = brotli::CompressorWriter::new(Vec::new(), 4096, 5, 22);
+    brotli.write_all(&data).unwrap();
+    let brotli = brotli.into_inner();
+
+    let files: [(&str, &[u8]); 4] = [
+        ("tiles/0/0/0.pbf.gz", &gzip),
+        ("tiles/0/0/0.pbf.zst", &zstd),
+        ("tiles/0/0/0.pbf.br", &brotli),
+        ("tiles/0/0/0.pbf", &data),
+    ];
+    let archive = create_archive("decode", &files);
+    let mut cotar = Cotar::from_tar(archive.embedded()).unwrap();
+
+    // Raw bytes are returned until decoding is enabled
+    for (path, raw) in files {
+        assert_eq!(cotar.get(path).unwrap().unwrap().as_ref(), raw);
+    }
+    cotar.set_decode(true);
+    let paths: Vec<&str> = files.iter().map(|(path, _)| *path).collect();
+    for (path, file) in paths.iter().zip(cotar.get_many(&paths, 1024).unwrap()) {
+        assert_eq!(cotar.get(path).unwrap().unwrap().as_ref(), data);
+        assert_eq!(file.unwrap().as_ref(), data);
+    }
+
+    let encodings = [
+        Some(ContentEncoding::Gzip),
+        Some(ContentEncoding::Zstd),
+        Some(ContentEncoding::Brotli),
+        None,
+    ];
+    for ((path, raw), expected) in files.iter().zip(encodings) {
+        let (bytes, encoding) = cotar.get_encoded(path).unwrap().unwrap();
+        assert_eq!(bytes.as_ref(), *raw);
+        assert_eq!(encoding, expected);
+    }
+
+    // Decoded files are limited by the max file size
+    cotar.set_max_file_size(data.len() as u64 - 1);
+    let err = cotar.get("tiles/0/0/0.pbf.gz").unwrap_err();
+    assert!(matches!(err, CotarError::FileTooLarge { .. }));
+
+    let err = ContentEncoding::Gzip.decode(&gzip[..10], 1024).unwrap_err();
+    assert!(matches!(err, CotarError::Decode(ContentEncoding::Gzip, _)));
+}
+
+#[cfg(not(feature = "gzip"))]
+#[test]
+fn test_decode_unsupported() {
+    let err = ContentEncoding::Gzip.decode(&GZIP_MAGIC, 1024).unwrap_err();
+    assert!(matches!(
+        err,
+        CotarError::UnsupportedEncoding(ContentEncoding::Gzip)
+    ));
+    assert!(!ContentEncoding::Gzip.is_supported());
+}
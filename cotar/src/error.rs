@@ -0,0 +1,193 @@
+use std::fmt;
+use std::io;
+
+use cotar_core::CoreError;
+
+use crate::encoding::ContentEncoding;
+
+/// Errors from reading or building a cotar index
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum CotarError {
+    /// Index header does not start with "COT"
+    InvalidMagic,
+    /// Index version is not supported by this reader
+    UnsupportedVersion(u8),
+    /// Path hash algorithm is not known or cannot be stored in the index version
+    UnsupportedHashAlgorithm(u8),
+    /// Index is shorter than its header describes
+    TruncatedIndex,
+    /// Index entry points outside of the tar archive
+    OutOfBounds { file_offset: u64, file_size: u64 },
+    /// Path has already been added to the index
+    DuplicatePath(String),
+    /// Path table next to the index is corrupt or does not match the index
+    InvalidPathTable,
+    /// Path of a index entry was not recorded so it cannot be written to the path table
+    MissingPath(u64),
+    /// Path hash was found in the index but the tar header names another file
+    HashCollision(String),
+    /// Extension headers in front of the file are too large to read to verify its path
+    UnverifiedPath(String),
+    /// Link target has not been added to the index
+    MissingLinkTarget { path: String, target: String },
+    /// Entry offset or size cannot be stored in the index version
+    EntryOverflow {
+        version: u8,
+        file_offset: u64,
+        file_size: u64,
+    },
+    /// File is larger than the limit set with `set_max_file_size`
+    FileTooLarge { file_size: u64, max_file_size: u64 },
+    /// Tar header in front of a file could not be parsed
+    InvalidTarHeader { file_offset: u64 },
+    /// Decoding the file needs a feature that is not enabled
+    UnsupportedEncoding(ContentEncoding),
+    /// Compressed file could not be decoded
+    Decode(ContentEncoding, io::Error),
+    /// Index cannot be packed into less slots than entries
+    PackingFactorTooLow(f64),
+    /// Index has more slots than can be stored in the header
+    TooManyEntries(u64),
+    /// Every slot of the index is in use
+    IndexFull,
+    /// Underlying I/O error from the source
+    Io(io::Error),
+}
+
+/// Result type of the cotar library
+pub type CotarResult<T> = std::result::Result<T, CotarError>;
+
+impl fmt::Display for CotarError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CotarError::InvalidMagic => write!(f, "Invalid magic"),
+            CotarError::UnsupportedVersion(version) => {
+                write!(f, "Unsupported version: {}", version)
+            }
+            CotarError::UnsupportedHashAlgorithm(id) => {
+                write!(f, "Unsupported hash algorithm: {}", id)
+            }
+            CotarError::TruncatedIndex => write!(f, "Truncated index"),
+            CotarError::OutOfBounds {
+                file_offset,
+                file_size,
+            } => write!(
+                f,
+                "Entry out of bounds offset: {} size: {}",
+                file_offset, file_size
+            ),
+            CotarError::DuplicatePath(path) => write!(f, "Duplicate hash key : {}", path),
+            CotarError::InvalidPathTable => write!(f, "Invalid path table"),
+            CotarError::MissingPath(hash) => {
+                write!(f, "Missing path for path table hash: {}", hash)
+            }
+            CotarError::HashCollision(path) => {
+                write!(f, "Hash collision: {} does not match the tar header", path)
+            }
+            CotarError::UnverifiedPath(path) => {
+                write!(f, "Cannot verify path: {} tar header is too large", path)
+            }
+            CotarError::MissingLinkTarget { path, target } => {
+                write!(f, "Missing link target: {} -> {}", path, target)
+            }
+            CotarError::EntryOverflow {
+                version,
+                file_offset,
+                file_size,
+            } => write!(
+                f,
+                "Entry does not fit in a v{} index offset: {} size: {}",
+                version, file_offset, file_size
+            ),
+            CotarError::FileTooLarge {
+                file_size,
+                max_file_size,
+            } => write!(
+                f,
+                "File too large size: {} max: {}",
+                file_size, max_file_size
+            ),
+            CotarError::InvalidTarHeader { file_offset } => {
+                write!(f, "Invalid tar header for file at offset: {}", file_offset)
+            }
+            CotarError::UnsupportedEncoding(encoding) => {
+                write!(f, "Unsupported content encoding: {}", encoding)
+            }
+            CotarError::Decode(encoding, e) => write!(f, "Failed to decode {}: {}", encoding, e),
+            CotarError::PackingFactorTooLow(factor) => {
+                write!(f, "Packing factor too low: {}", factor)
+            }
+            CotarError::TooManyEntries(slots) => write!(f, "Too many index entries: {}", slots),
+            CotarError::IndexFull => write!(f, "Hash index looped"),
+            CotarError::Io(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for CotarError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            CotarError::Io(e) | CotarError::Decode(_, e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for CotarError {
+    fn from(e: io::Error) -> Self {
+        CotarError::Io(e)
+    }
+}
+
+impl From<CoreError> for CotarError {
+    fn from(e: CoreError) -> Self {
+        match e {
+            CoreError::InvalidMagic => CotarError::InvalidMagic,
+            CoreError::UnsupportedVersion(version) => CotarError::UnsupportedVersion(version),
+            CoreError::UnsupportedHashAlgorithm(id) => CotarError::UnsupportedHashAlgorithm(id),
+            CoreError::TruncatedIndex => CotarError::TruncatedIndex,
+            CoreError::OutOfBounds {
+                file_offset,
+                file_size,
+            } => CotarError::OutOfBounds {
+                file_offset,
+                file_size,
+            },
+            e => CotarError::Io(io::Error::new(io::ErrorKind::UnexpectedEof, e)),
+        }
+    }
+}
+
+impl From<CotarError> for io::Error {
+    fn from(e: CotarError) -> Self {
+        match e {
+            CotarError::Io(e) => e,
+            CotarError::DuplicatePath(_)
+            | CotarError::MissingLinkTarget { .. }
+            | CotarError::EntryOverflow { .. }
+            | CotarError::MissingPath(_)
+            | CotarError::PackingFactorTooLow(_)
+            | CotarError::TooManyEntries(_)
+            | CotarError::IndexFull => io::Error::new(io::ErrorKind::InvalidInput, e),
+            CotarError::UnsupportedEncoding(_) => io::Error::new(io::ErrorKind::Unsupported, e),
+            e => io::Error::new(io::ErrorKind::InvalidData, e),
+        }
+    }
+}
+
+#[test]
+fn test_io_error_conversion() {
+    let err = io::Error::from(CotarError::Io(io::Error::new(
+        io::ErrorKind::NotFound,
+        "missing",
+    )));
+    assert_eq!(err.kind(), io::ErrorKind::NotFound);
+
+    let err = io::Error::from(CotarError::InvalidMagic);
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    assert_eq!(err.to_string(), "Invalid magic");
+
+    let err = CotarError::from(io::Error::other("failed"));
+    assert!(matches!(err, CotarError::Io(_)));
+}
@@ -0,0 +1,155 @@
+use std::io::Read;
+use std::io::Result as IoResult;
+use std::io::{Error, ErrorKind};
+
+use bytes::Bytes;
+
+use crate::cotar::{Cotar, DEFAULT_TAIL_SIZE};
+use crate::error::CotarResult;
+use crate::source::Source;
+
+/// Read only view of a remote file using HTTP range requests
+#[derive(Debug, Clone)]
+pub struct HttpView {
+    agent: ureq::Agent,
+    url: String,
+    pub size: u64,
+}
+
+impl HttpView {
+    /// Open a remote file, the size of the file is read with a HEAD request
+    pub fn open(url: &str) -> IoResult<Self> {
+        HttpView::open_with_agent(ureq::Agent::new(), url)
+    }
+
+    /// Open a remote file using a preconfigured agent, for example with custom timeouts or proxies
+    pub fn open_with_agent(agent: ureq::Agent, url: &str) -> IoResult<Self> {
+        let response = agent.head(url).call().map_err(to_io_error)?;
+        let size = response
+            .header("Content-Length")
+            .and_then(|len| len.parse::<u64>().ok())
+            .ok_or_else(|| Error::other(format!("Missing Content-Length: {}", url)))?;
+
+        Ok(HttpView {
+            agent,
+            url: url.to_string(),
+            size,
+        })
+    }
+
+    pub fn read_exact(&self, offset: u64, len: u64) -> IoResult<Bytes> {
+        if len == 0 {
+            return Ok(Bytes::new());
+        }
+        let end = offset
+            .checked_add(len)
+            .filter(|end| *end <= self.size)
+            .ok_or_else(|| Error::new(ErrorKind::UnexpectedEof, "failed to fill whole buffer"))?;
+
+        let response = self
+            .agent
+            .get(&self.url)
+            .set("Range", &format!("bytes={}-{}", offset, end - 1))
+            .call()
+            .map_err(to_io_error)?;
+
+        // A server that ignores the range header will return the entire file
+        if response.status() != 206 {
+            return Err(Error::other(format!(
+                "Range request not supported, status: {} url: {}",
+                response.status(),
+                self.url
+            )));
+        }
+
+        let mut buf = vec![0; len as usize];
+        response.into_reader().read_exact(&mut buf)?;
+        Ok(Bytes::from(buf))
+    }
+}
+
+fn to_io_error(e: ureq::Error) -> Error {
+    match e {
+        ureq::Error::Status(404, _) => Error::new(ErrorKind::NotFound, e.to_string()),
+        e => Error::other(e.to_string()),
+    }
+}
+
+impl Source for HttpView {
+    fn size(&self) -> u64 {
+        self.size
+    }
+
+    fn read_exact(&self, offset: u64, len: u64) -> IoResult<Bytes> {
+        HttpView::read_exact(self, offset, len)
+    }
+}
+
+impl Cotar<HttpView> {
+    /// Load a cotar from a remote packed tar file
+    ///
+    /// The index of the tar must be the final bytes of the tar file, the last `DEFAULT_TAIL_SIZE`
+    /// bytes are read with a single request see `Cotar::from_source_with_tail`
+    pub fn from_url(url: &str) -> CotarResult<Self> {
+        Cotar::from_source_with_tail(HttpView::open(url)?, DEFAULT_TAIL_SIZE)
+    }
+
+    /// Load a cotar from a remote tar and index file
+    pub fn from_url_index(tar_url: &str, index_url: &str) -> CotarResult<Self> {
+        Cotar::from_sources(HttpView::open(tar_url)?, HttpView::open(index_url)?)
+    }
+}
+
+#[test]
+fn test_http_range_reads() {
+    use crate::test_util::{create_archive, serve_files, TEST_FILES};
+
+    let archive = create_archive("http_range_reads", &TEST_FILES);
+    let server = serve_files(&[
+        ("/archive.tar.co", std::fs::read(&archive.embedded).unwrap()),
+        ("/archive.tar", std::fs::read(&archive.tar).unwrap()),
+        ("/archive.tar.index", std::fs::read(&archive.index).unwrap()),
+    ]);
+
+    for cotar in [
+        Cotar::from_url(&server.url("/archive.tar.co")).unwrap(),
+        Cotar::from_url_index(
+            &server.url("/archive.tar"),
+            &server.url("/archive.tar.index"),
+        )
+        .unwrap(),
+    ] {
+        crate::test_util::assert_test_files(&cotar);
+    }
+}
+
+#[test]
+fn test_http_tail_read() {
+    use crate::test_util::{create_archive, serve_files, TEST_FILES};
+
+    let archive = create_archive("http_tail_read", &TEST_FILES);
+    let server = serve_files(&[("/archive.tar.co", std::fs::read(&archive.embedded).unwrap())]);
+
+    // The whole index is read with the first range request
+    let cotar = Cotar::from_url(&server.url("/archive.tar.co")).unwrap();
+    assert!(cotar.is_index_loaded());
+    assert_eq!(server.requests(), 1);
+    crate::test_util::assert_test_files(&cotar);
+    assert_eq!(server.requests(), 1 + TEST_FILES.len());
+}
+
+#[test]
+fn test_http_missing() {
+    let server = crate::test_util::serve_files(&[]);
+    let err = HttpView::open(&server.url("/missing.tar")).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+}
+
+#[test]
+fn test_http_out_of_bounds() {
+    let server = crate::test_util::serve_files(&[("/data", b"hello world".to_vec())]);
+    let view = HttpView::open(&server.url("/data")).unwrap();
+    assert_eq!(view.size, 11);
+    assert_eq!(view.read_exact(0, 5).unwrap().as_ref(), b"hello");
+    assert!(view.read_exact(6, 6).is_err());
+}
@@ -0,0 +1,162 @@
+use std::io::Result as IoResult;
+use std::io::{Error, ErrorKind};
+
+use bytes::Bytes;
+use reqwest::header::{CONTENT_LENGTH, RANGE};
+use reqwest::StatusCode;
+
+use crate::async_cotar::{AsyncCotar, AsyncSource};
+use crate::cotar::DEFAULT_TAIL_SIZE;
+use crate::error::CotarResult;
+
+/// Read only view of a remote file using async HTTP range requests
+#[derive(Debug, Clone)]
+pub struct AsyncHttpView {
+    client: reqwest::Client,
+    url: String,
+    pub size: u64,
+}
+
+impl AsyncHttpView {
+    /// Open a remote file, the size of the file is read with a HEAD request
+    pub async fn open(url: &str) -> IoResult<Self> {
+        AsyncHttpView::open_with_client(reqwest::Client::new(), url).await
+    }
+
+    /// Open a remote file using a preconfigured client, for example with custom timeouts or proxies
+    pub async fn open_with_client(client: reqwest::Client, url: &str) -> IoResult<Self> {
+        let response = client.head(url).send().await.map_err(to_io_error)?;
+        let response = response.error_for_status().map_err(to_io_error)?;
+        let size = response
+            .headers()
+            .get(CONTENT_LENGTH)
+            .and_then(|len| len.to_str().ok())
+            .and_then(|len| len.parse::<u64>().ok())
+            .ok_or_else(|| Error::other(format!("Missing Content-Length: {}", url)))?;
+
+        Ok(AsyncHttpView {
+            client,
+            url: url.to_string(),
+            size,
+        })
+    }
+}
+
+fn to_io_error(e: reqwest::Error) -> Error {
+    match e.status() {
+        Some(StatusCode::NOT_FOUND) => Error::new(ErrorKind::NotFound, e.to_string()),
+        _ => Error::other(e.to_string()),
+    }
+}
+
+impl AsyncSource for AsyncHttpView {
+    fn size(&self) -> u64 {
+        self.size
+    }
+
+    async fn read_exact(&self, offset: u64, len: u64) -> IoResult<Bytes> {
+        if len == 0 {
+            return Ok(Bytes::new());
+        }
+        let end = offset
+            .checked_add(len)
+            .filter(|end| *end <= self.size)
+            .ok_or_else(|| Error::new(ErrorKind::UnexpectedEof, "failed to fill whole buffer"))?;
+
+        let response = self
+            .client
+            .get(&self.url)
+            .header(RANGE, format!("bytes={}-{}", offset, end - 1))
+            .send()
+            .await
+            .map_err(to_io_error)?;
+        let response = response.error_for_status().map_err(to_io_error)?;
+
+        // A server that ignores the range header will return the entire file
+        if response.status() != StatusCode::PARTIAL_CONTENT {
+            return Err(Error::other(format!(
+                "Range request not supported, status: {} url: {}",
+                response.status(),
+                self.url
+            )));
+        }
+
+        let bytes = response.bytes().await.map_err(to_io_error)?;
+        if bytes.len() as u64 != len {
+            return Err(Error::new(
+                ErrorKind::UnexpectedEof,
+                "failed to fill whole buffer",
+            ));
+        }
+        Ok(bytes)
+    }
+}
+
+impl AsyncCotar<AsyncHttpView> {
+    /// Load a cotar from a remote packed tar file
+    ///
+    /// The index of the tar must be the final bytes of the tar file, the last `DEFAULT_TAIL_SIZE`
+    /// bytes are read with a single request see `Cotar::from_source_with_tail`
+    pub async fn from_url(url: &str) -> CotarResult<Self> {
+        AsyncCotar::from_source_with_tail(AsyncHttpView::open(url).await?, DEFAULT_TAIL_SIZE).await
+    }
+
+    /// Load a cotar from a remote tar and index file
+    pub async fn from_url_index(tar_url: &str, index_url: &str) -> CotarResult<Self> {
+        AsyncCotar::from_sources(
+            AsyncHttpView::open(tar_url).await?,
+            AsyncHttpView::open(index_url).await?,
+        )
+        .await
+    }
+}
+
+#[tokio::test]
+async fn test_async_http_range_reads() {
+    use crate::test_util::{create_archive, serve_files, TEST_FILES};
+
+    let archive = create_archive("async_http_range_reads", &TEST_FILES);
+    let server = serve_files(&[
+        ("/archive.tar.co", std::fs::read(&archive.embedded).unwrap()),
+        ("/archive.tar", std::fs::read(&archive.tar).unwrap()),
+        ("/archive.tar.index", std::fs::read(&archive.index).unwrap()),
+    ]);
+
+    for cotar in [
+        AsyncCotar::from_url(&server.url("/archive.tar.co"))
+            .await
+            .unwrap(),
+        AsyncCotar::from_url_index(
+            &server.url("/archive.tar"),
+            &server.url("/archive.tar.index"),
+        )
+        .await
+        .unwrap(),
+    ] {
+        crate::test_util::assert_test_files_async(&cotar).await;
+    }
+}
+
+#[tokio::test]
+async fn test_async_http_tail_read() {
+    use crate::test_util::{create_archive, serve_files, TEST_FILES};
+
+    let archive = create_archive("async_http_tail_read", &TEST_FILES);
+    let server = serve_files(&[("/archive.tar.co", std::fs::read(&archive.embedded).unwrap())]);
+
+    // The whole index is read with the first range request
+    let cotar = AsyncCotar::from_url(&server.url("/archive.tar.co"))
+        .await
+        .unwrap();
+    assert!(cotar.is_index_loaded());
+    assert_eq!(server.requests(), 1);
+}
+
+#[tokio::test]
+async fn test_async_http_missing() {
+    let server = crate::test_util::serve_files(&[]);
+    let err = AsyncHttpView::open(&server.url("/missing.tar"))
+        .await
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+}
@@ -1,8 +1,43 @@
+mod archive;
+#[cfg(feature = "tokio")]
+mod async_cotar;
+mod cache;
 mod cotar;
 mod cotar_index;
 mod dataview;
-mod fnv1a;
+mod encoding;
+mod error;
+#[cfg(feature = "http")]
+mod http;
+#[cfg(feature = "http-async")]
+mod http_async;
+mod path_table;
+mod reader;
+mod source;
+mod stat;
+#[cfg(feature = "object_store")]
+mod store;
+mod tar_header;
 
+#[cfg(test)]
+mod test_util;
+
+#[cfg(feature = "tokio")]
+pub use crate::async_cotar::*;
+pub use crate::cache::*;
 pub use crate::cotar::*;
 pub use crate::cotar_index::*;
-pub use crate::fnv1a::*;
+pub use crate::dataview::DataView;
+pub use crate::encoding::ContentEncoding;
+pub use crate::error::*;
+#[cfg(feature = "http")]
+pub use crate::http::HttpView;
+#[cfg(feature = "http-async")]
+pub use crate::http_async::AsyncHttpView;
+pub use crate::path_table::{PathTable, COTAR_PATH_TABLE_HEADER_SIZE, COTAR_PATH_TABLE_MAGIC};
+pub use crate::reader::EntryReader;
+pub use crate::source::*;
+pub use crate::stat::{CotarEntryType, CotarStat};
+#[cfg(feature = "object_store")]
+pub use crate::store::ObjectStoreView;
+pub use crate::tar_header::{TAR_EXTENSION_LOOKBACK, TAR_HEADER_SIZE};
@@ -0,0 +1,216 @@
+use bytes::{Buf, Bytes};
+
+use crate::error::{CotarError, CotarResult};
+
+/// "COTP" as a u32
+pub const COTAR_PATH_TABLE_MAGIC: u32 = 1347702595;
+
+/// Path tables start and end with the magic, slot count and total size of the table
+pub const COTAR_PATH_TABLE_HEADER_SIZE: u64 = 16;
+
+/// Table mapping the slots of a hash index to their UTF-8 paths
+///
+/// The table is written next to the hash index, before a embedded index or after the footer of a
+/// separate index file, so readers that do not know about it still find the index.
+///
+/// The header is followed by a u64 offset for every slot plus a final end offset, then the paths.
+/// Empty slots have a empty path
+#[derive(Debug, Clone)]
+pub struct PathTable {
+    slots: u64,
+    offsets: Bytes,
+    paths: Bytes,
+}
+
+/// Slot count and total size of a path table from its header or footer
+pub(crate) fn table_header(mut bytes: Bytes) -> Option<(u64, u64)> {
+    if (bytes.len() as u64) < COTAR_PATH_TABLE_HEADER_SIZE
+        || bytes.get_u32_le() != COTAR_PATH_TABLE_MAGIC
+    {
+        return None;
+    }
+    let slots = bytes.get_u32_le() as u64;
+    let size = bytes.get_u64_le();
+    Some((slots, size))
+}
+
+/// Offset of the header or footer of a path table next to a index
+///
+/// Separate index files have the table after the index, embedded indexes have the table before the index
+pub(crate) fn frame_offset(
+    index_offset: u64,
+    index_size: u64,
+    source_size: u64,
+    separate: bool,
+) -> Option<u64> {
+    match separate {
+        true => Some(index_offset + index_size)
+            .filter(|offset| offset + COTAR_PATH_TABLE_HEADER_SIZE <= source_size),
+        false => index_offset.checked_sub(COTAR_PATH_TABLE_HEADER_SIZE),
+    }
+}
+
+/// Offset and size of a path table from the header or footer read at `frame_offset`
+///
+/// Returns None if there is no path table
+pub(crate) fn table_range(
+    frame: Bytes,
+    frame_offset: u64,
+    source_size: u64,
+    separate: bool,
+) -> CotarResult<Option<(u64, u64)>> {
+    let Some((_, size)) = table_header(frame) else {
+        return Ok(None);
+    };
+    let offset = match separate {
+        true => Some(frame_offset),
+        false => (frame_offset + COTAR_PATH_TABLE_HEADER_SIZE).checked_sub(size),
+    };
+    match offset {
+        Some(offset)
+            if offset
+                .checked_add(size)
+                .is_some_and(|end| end <= source_size) =>
+        {
+            Ok(Some((offset, size)))
+        }
+        _ => Err(CotarError::InvalidPathTable),
+    }
+}
+
+impl PathTable {
+    /// Parse a path table, `slots` must match the slot count of the hash index
+    pub fn from_bytes(bytes: Bytes, slots: u64) -> CotarResult<Self> {
+        let header_size = COTAR_PATH_TABLE_HEADER_SIZE as usize;
+        let size = bytes.len();
+        let header = table_header(bytes.slice(..header_size.min(size)));
+        let footer = table_header(bytes.slice(size.saturating_sub(header_size)..));
+        if header.is_none() || header != footer || header != Some((slots, size as u64)) {
+            return Err(CotarError::InvalidPathTable);
+        }
+
+        let offsets_size = slots
+            .checked_add(1)
+            .and_then(|count| count.checked_mul(8))
+            .filter(|offsets_size| offsets_size + COTAR_PATH_TABLE_HEADER_SIZE * 2 <= size as u64)
+            .ok_or(CotarError::InvalidPathTable)? as usize;
+        let offsets = bytes.slice(header_size..header_size + offsets_size);
+        let paths = bytes.slice(header_size + offsets_size..size - header_size);
+
+        // Offsets must only go forwards and end at the end of the paths
+        let mut previous = 0;
+        for mut offset in offsets.chunks_exact(8) {
+            let offset = offset.get_u64_le();
+            if offset < previous || offset > paths.len() as u64 {
+                return Err(CotarError::InvalidPathTable);
+            }
+            previous = offset;
+        }
+        if previous != paths.len() as u64 {
+            return Err(CotarError::InvalidPathTable);
+        }
+
+        Ok(PathTable {
+            slots,
+            offsets,
+            paths,
+        })
+    }
+
+    /// Pack the path of every slot into a path table
+    pub fn pack(slot_paths: &[Option<&str>]) -> Vec<u8> {
+        let paths_size: usize = slot_paths.iter().flatten().map(|path| path.len()).sum();
+        let size =
+            COTAR_PATH_TABLE_HEADER_SIZE as usize * 2 + (slot_paths.len() + 1) * 8 + paths_size;
+
+        let mut header = Vec::with_capacity(COTAR_PATH_TABLE_HEADER_SIZE as usize);
+        header.extend_from_slice(&COTAR_PATH_TABLE_MAGIC.to_le_bytes());
+        header.extend_from_slice(&(slot_paths.len() as u32).to_le_bytes());
+        header.extend_from_slice(&(size as u64).to_le_bytes());
+
+        let mut output = Vec::with_capacity(size);
+        output.extend_from_slice(&header);
+        let mut offset: u64 = 0;
+        output.extend_from_slice(&offset.to_le_bytes());
+        for path in slot_paths {
+            offset += path.map_or(0, |path| path.len() as u64);
+            output.extend_from_slice(&offset.to_le_bytes());
+        }
+        for path in slot_paths.iter().flatten() {
+            output.extend_from_slice(path.as_bytes());
+        }
+        output.extend_from_slice(&header);
+        output
+    }
+
+    /// Number of slots in the hash index
+    pub fn slot_count(&self) -> u64 {
+        self.slots
+    }
+
+    /// Path stored in a slot of the hash index
+    ///
+    /// Returns None if the slot is empty or the path is not valid UTF-8
+    pub fn path(&self, slot: u64) -> Option<&str> {
+        if slot >= self.slots {
+            return None;
+        }
+        let offset = (slot * 8) as usize;
+        let start = (&self.offsets[offset..]).get_u64_le() as usize;
+        let end = (&self.offsets[offset + 8..]).get_u64_le() as usize;
+        match start == end {
+            true => None,
+            false => std::str::from_utf8(&self.paths[start..end]).ok(),
+        }
+    }
+
+    /// Iterate the slot and path of every file in the table
+    pub fn iter(&self) -> impl Iterator<Item = (u64, &str)> {
+        (0..self.slots).filter_map(|slot| self.path(slot).map(|path| (slot, path)))
+    }
+
+    /// Iterate the slot and path of every file whose path starts with `prefix`
+    pub fn with_prefix<'a>(&'a self, prefix: &'a str) -> impl Iterator<Item = (u64, &'a str)> {
+        self.iter()
+            .filter(move |(_, path)| path.starts_with(prefix))
+    }
+
+    /// Number of files in the table
+    pub fn len(&self) -> usize {
+        self.iter().count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.iter().next().is_none()
+    }
+}
+
+#[test]
+fn test_path_table() {
+    let slot_paths = [
+        Some("tiles/0/0/0.pbf"),
+        None,
+        Some("tiles/1/0/0.pbf"),
+        Some("🦄.txt"),
+    ];
+    let packed = Bytes::from(PathTable::pack(&slot_paths));
+    let table = PathTable::from_bytes(packed.clone(), 4).unwrap();
+
+    assert_eq!(table.slot_count(), 4);
+    assert_eq!(table.len(), 3);
+    for (slot, path) in slot_paths.iter().enumerate() {
+        assert_eq!(table.path(slot as u64), *path);
+    }
+    assert_eq!(table.path(4), None);
+
+    let tiles: Vec<_> = table.with_prefix("tiles/").collect();
+    assert_eq!(tiles, vec![(0, "tiles/0/0/0.pbf"), (2, "tiles/1/0/0.pbf")]);
+
+    // Slot count must match the index
+    assert!(PathTable::from_bytes(packed.clone(), 5).is_err());
+    // Truncated or corrupt tables are rejected
+    assert!(PathTable::from_bytes(packed.slice(..packed.len() - 1), 4).is_err());
+    let mut corrupt = packed.to_vec();
+    corrupt[16..24].copy_from_slice(&u64::MAX.to_le_bytes());
+    assert!(PathTable::from_bytes(Bytes::from(corrupt), 4).is_err());
+}
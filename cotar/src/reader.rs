@@ -0,0 +1,127 @@
+use std::io::Result as IoResult;
+use std::io::{Error, ErrorKind, Read, Seek, SeekFrom};
+
+use crate::source::Source;
+use cotar_core::CotarIndexEntry;
+
+/// Reader over a single file of a tar archive
+///
+/// Reads are bounded to the file and are passed directly to the source,
+/// wrap the reader in a `std::io::BufReader` when making lots of small reads
+#[derive(Debug)]
+pub struct EntryReader<'a, S> {
+    view: &'a S,
+    /// Offset of the file in the tar archive
+    file_offset: u64,
+    /// Size of the file in bytes
+    file_size: u64,
+    /// Current position relative to the start of the file
+    position: u64,
+}
+
+impl<'a, S: Source> EntryReader<'a, S> {
+    pub fn new(view: &'a S, entry: &CotarIndexEntry) -> Self {
+        EntryReader {
+            view,
+            file_offset: entry.file_offset,
+            file_size: entry.file_size,
+            position: 0,
+        }
+    }
+
+    /// Size of the file in bytes
+    pub fn len(&self) -> u64 {
+        self.file_size
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.file_size == 0
+    }
+}
+
+impl<S: Source> Read for EntryReader<'_, S> {
+    fn read(&mut self, buf: &mut [u8]) -> IoResult<usize> {
+        let remaining = self.file_size.saturating_sub(self.position);
+        let len = remaining.min(buf.len() as u64);
+        if len == 0 {
+            return Ok(0);
+        }
+
+        let bytes = self
+            .view
+            .read_exact(self.file_offset + self.position, len)?;
+        buf[..len as usize].copy_from_slice(&bytes);
+        self.position += len;
+        Ok(len as usize)
+    }
+}
+
+impl<S: Source> Seek for EntryReader<'_, S> {
+    fn seek(&mut self, pos: SeekFrom) -> IoResult<u64> {
+        let position = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(offset) => self.file_size.checked_add_signed(offset),
+            SeekFrom::Current(offset) => self.position.checked_add_signed(offset),
+        };
+
+        match position {
+            None => Err(Error::new(
+                ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position",
+            )),
+            Some(position) => {
+                self.position = position;
+                Ok(position)
+            }
+        }
+    }
+}
+
+#[test]
+fn test_entry_reader() {
+    use crate::test_util::{create_archive, TEST_FILES};
+    use crate::Cotar;
+
+    let archive = create_archive("entry_reader", &TEST_FILES);
+    let cotar = Cotar::from_tar(archive.embedded()).unwrap();
+
+    for (path, data) in TEST_FILES {
+        let mut reader = cotar.reader(path).unwrap().unwrap();
+        assert_eq!(reader.len(), data.len() as u64);
+
+        let mut output = Vec::new();
+        reader.read_to_end(&mut output).unwrap();
+        assert_eq!(output, data);
+    }
+    assert!(cotar.reader("tiles/2/0/0.pbf").unwrap().is_none());
+}
+
+#[test]
+fn test_entry_reader_seek() {
+    use crate::test_util::{create_archive, TEST_FILES};
+    use crate::Cotar;
+
+    let archive = create_archive("entry_reader_seek", &TEST_FILES);
+    let cotar = Cotar::from_tar(archive.embedded()).unwrap();
+
+    // "tile 1/0/0 with a little more data"
+    let mut reader = cotar.reader("tiles/1/0/0.pbf").unwrap().unwrap();
+    let mut buf = [0; 4];
+
+    assert_eq!(reader.seek(SeekFrom::Start(5)).unwrap(), 5);
+    reader.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"1/0/");
+
+    assert_eq!(reader.seek(SeekFrom::End(-4)).unwrap(), 30);
+    reader.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"data");
+
+    assert_eq!(reader.seek(SeekFrom::Current(-9)).unwrap(), 25);
+    reader.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"more");
+
+    // Reads past the end of the file stop at the end of the file
+    reader.seek(SeekFrom::End(10)).unwrap();
+    assert_eq!(reader.read(&mut buf).unwrap(), 0);
+    assert!(reader.seek(SeekFrom::Current(-100)).is_err());
+}
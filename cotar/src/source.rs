@@ -0,0 +1,103 @@
+use std::io::Result as IoResult;
+use std::io::{Error, ErrorKind};
+use std::sync::Arc;
+
+use bytes::Bytes;
+
+use crate::dataview::DataView;
+
+/// Random access source of bytes that a `Cotar` can be read from
+///
+/// Implementations must be safe to read from multiple threads at once
+pub trait Source {
+    /// Total size of the source in bytes
+    fn size(&self) -> u64;
+
+    /// Read exactly `len` bytes starting at `offset`
+    fn read_exact(&self, offset: u64, len: u64) -> IoResult<Bytes>;
+}
+
+impl Source for DataView {
+    fn size(&self) -> u64 {
+        self.size
+    }
+
+    fn read_exact(&self, offset: u64, len: u64) -> IoResult<Bytes> {
+        DataView::read_exact(self, offset, len)
+    }
+}
+
+/// In memory buffer
+impl Source for Bytes {
+    fn size(&self) -> u64 {
+        self.len() as u64
+    }
+
+    fn read_exact(&self, offset: u64, len: u64) -> IoResult<Bytes> {
+        let end = offset.checked_add(len).filter(|end| *end <= self.size());
+        match end {
+            None => Err(Error::new(
+                ErrorKind::UnexpectedEof,
+                "failed to fill whole buffer",
+            )),
+            Some(end) => Ok(self.slice(offset as usize..end as usize)),
+        }
+    }
+}
+
+impl<S: Source + ?Sized> Source for &S {
+    fn size(&self) -> u64 {
+        (**self).size()
+    }
+
+    fn read_exact(&self, offset: u64, len: u64) -> IoResult<Bytes> {
+        (**self).read_exact(offset, len)
+    }
+}
+
+impl<S: Source + ?Sized> Source for Box<S> {
+    fn size(&self) -> u64 {
+        (**self).size()
+    }
+
+    fn read_exact(&self, offset: u64, len: u64) -> IoResult<Bytes> {
+        (**self).read_exact(offset, len)
+    }
+}
+
+impl<S: Source + ?Sized> Source for Arc<S> {
+    fn size(&self) -> u64 {
+        (**self).size()
+    }
+
+    fn read_exact(&self, offset: u64, len: u64) -> IoResult<Bytes> {
+        (**self).read_exact(offset, len)
+    }
+}
+
+#[test]
+fn test_bytes_read_exact() {
+    let source = Bytes::from_static(b"hello world");
+    assert_eq!(source.size(), 11);
+    assert_eq!(source.read_exact(6, 5).unwrap().as_ref(), b"world");
+    assert!(source.read_exact(6, 6).is_err());
+    assert!(source.read_exact(u64::MAX, 1).is_err());
+}
+
+#[test]
+fn test_cotar_from_bytes() {
+    use crate::test_util::{create_archive, TEST_FILES};
+    use crate::Cotar;
+
+    let archive = create_archive("cotar_from_bytes", &TEST_FILES);
+    let embedded = Bytes::from(std::fs::read(&archive.embedded).unwrap());
+    let tar = Bytes::from(std::fs::read(&archive.tar).unwrap());
+    let index = Bytes::from(std::fs::read(&archive.index).unwrap());
+
+    for cotar in [
+        Cotar::from_source(embedded).unwrap(),
+        Cotar::from_sources(tar, index).unwrap(),
+    ] {
+        crate::test_util::assert_test_files(&cotar);
+    }
+}
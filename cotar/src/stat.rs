@@ -0,0 +1,186 @@
+use std::time::{Duration, SystemTime};
+
+use tar::EntryType;
+
+use crate::tar_header::TarHeader;
+use cotar_core::CotarIndexEntry;
+
+/// Type of a tar entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CotarEntryType {
+    Regular,
+    HardLink,
+    Symlink,
+    Char,
+    Block,
+    Directory,
+    Fifo,
+    /// Any other entry type byte
+    Other(u8),
+}
+
+impl From<EntryType> for CotarEntryType {
+    fn from(entry_type: EntryType) -> Self {
+        match entry_type {
+            EntryType::Regular | EntryType::Continuous => CotarEntryType::Regular,
+            EntryType::Link => CotarEntryType::HardLink,
+            EntryType::Symlink => CotarEntryType::Symlink,
+            EntryType::Char => CotarEntryType::Char,
+            EntryType::Block => CotarEntryType::Block,
+            EntryType::Directory => CotarEntryType::Directory,
+            EntryType::Fifo => CotarEntryType::Fifo,
+            entry_type => CotarEntryType::Other(entry_type.as_byte()),
+        }
+    }
+}
+
+/// Metadata of a file from the tar header in front of it
+///
+/// PAX extended header values are preferred over the values of the tar header.
+/// Files stored as hard links share the tar header of the file they link to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CotarStat {
+    /// Path from the tar header, invalid UTF-8 is replaced
+    pub path: String,
+    /// Target of a link
+    pub link_name: Option<String>,
+    pub entry_type: CotarEntryType,
+    /// Permission bits
+    pub mode: u32,
+    pub uid: u64,
+    pub gid: u64,
+    pub user_name: Option<String>,
+    pub group_name: Option<String>,
+    /// Modification time in seconds since the unix epoch
+    pub mtime: u64,
+    /// File offset
+    pub file_offset: u64,
+    /// File size
+    pub file_size: u64,
+}
+
+/// Whole seconds of a PAX decimal number, which may have a fractional part
+fn pax_seconds(value: &[u8]) -> Option<u64> {
+    let value = std::str::from_utf8(value).ok()?;
+    let seconds = value.split_once('.').map_or(value, |(seconds, _)| seconds);
+    seconds.parse().ok()
+}
+
+fn pax_string(value: &[u8]) -> Option<String> {
+    String::from_utf8(value.to_vec()).ok()
+}
+
+impl CotarStat {
+    /// Read the metadata of a entry from its tar header
+    ///
+    /// Numeric fields that are empty or cannot be parsed are read as 0
+    pub(crate) fn from_header(header: &TarHeader, entry: &CotarIndexEntry) -> Self {
+        let raw = &header.header;
+        let pax_number = |key| header.pax_value(key).and_then(pax_seconds);
+        let pax_name = |key| header.pax_value(key).and_then(pax_string);
+
+        CotarStat {
+            path: String::from_utf8_lossy(&header.path_bytes()).into_owned(),
+            link_name: header
+                .link_name_bytes()
+                .map(|link_name| String::from_utf8_lossy(&link_name).into_owned()),
+            entry_type: raw.entry_type().into(),
+            mode: raw.mode().unwrap_or_default(),
+            uid: pax_number("uid").unwrap_or_else(|| raw.uid().unwrap_or_default()),
+            gid: pax_number("gid").unwrap_or_else(|| raw.gid().unwrap_or_default()),
+            user_name: pax_name("uname")
+                .or_else(|| raw.username().ok().flatten().map(str::to_string)),
+            group_name: pax_name("gname")
+                .or_else(|| raw.groupname().ok().flatten().map(str::to_string)),
+            mtime: pax_number("mtime").unwrap_or_else(|| raw.mtime().unwrap_or_default()),
+            file_offset: entry.file_offset,
+            file_size: entry.file_size,
+        }
+    }
+
+    /// Modification time of the file, for example to build a HTTP `Last-Modified` header
+    pub fn modified(&self) -> SystemTime {
+        SystemTime::UNIX_EPOCH + Duration::from_secs(self.mtime)
+    }
+}
+
+#[test]
+fn test_stat() {
+    use bytes::Bytes;
+    use tar::{Archive, Builder, Header};
+
+    use crate::{Cotar, CotarIndex};
+
+    let mut builder = Builder::new(Vec::new());
+    let mut header = Header::new_gnu();
+    header.set_size(4);
+    header.set_mode(0o640);
+    header.set_uid(1000);
+    header.set_gid(100);
+    header.set_mtime(1_700_000_000);
+    header.set_username("tiles").unwrap();
+    builder
+        .append_data(&mut header, "tiles/0/0/0.pbf", &b"data"[..])
+        .unwrap();
+
+    builder
+        .append_pax_extensions([
+            ("mtime", b"1800000000.25".as_slice()),
+            ("uid", b"4000000000"),
+            ("gname", b"cotar"),
+        ])
+        .unwrap();
+    let mut header = Header::new_ustar();
+    header.set_size(4);
+    header.set_mode(0o644);
+    header.set_path("tiles/1/0/0.pbf").unwrap();
+    header.set_cksum();
+    builder.append(&header, &b"data"[..]).unwrap();
+
+    let mut header = Header::new_gnu();
+    header.set_size(0);
+    header.set_entry_type(EntryType::Symlink);
+    builder
+        .append_link(&mut header, "latest.pbf", "tiles/1/0/0.pbf")
+        .unwrap();
+    let tar = builder.into_inner().unwrap();
+
+    let mut index = CotarIndex::new();
+    let mut archive = Archive::new(tar.as_slice());
+    for entry in archive.entries().unwrap() {
+        let entry = entry.unwrap();
+        let path = entry.path().unwrap().to_str().unwrap().to_string();
+        index
+            .add(&path, entry.raw_file_position(), entry.size())
+            .unwrap();
+    }
+    let mut cotar_bytes = tar.clone();
+    cotar_bytes.extend_from_slice(&index.pack(1.25).unwrap().vec);
+    let cotar = Cotar::from_source(Bytes::from(cotar_bytes)).unwrap();
+
+    let stat = cotar.stat("tiles/0/0/0.pbf").unwrap().unwrap();
+    assert_eq!(stat.path, "tiles/0/0/0.pbf");
+    assert_eq!(stat.entry_type, CotarEntryType::Regular);
+    assert_eq!(stat.mode, 0o640);
+    assert_eq!((stat.uid, stat.gid), (1000, 100));
+    assert_eq!(stat.user_name.as_deref(), Some("tiles"));
+    assert_eq!(stat.mtime, 1_700_000_000);
+    assert_eq!(
+        stat.modified(),
+        SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000)
+    );
+    assert_eq!(stat.file_size, 4);
+
+    // PAX values replace the header values
+    let stat = cotar.stat("tiles/1/0/0.pbf").unwrap().unwrap();
+    assert_eq!(stat.mode, 0o644);
+    assert_eq!(stat.uid, 4_000_000_000);
+    assert_eq!(stat.group_name.as_deref(), Some("cotar"));
+    assert_eq!(stat.mtime, 1_800_000_000);
+
+    let stat = cotar.stat("latest.pbf").unwrap().unwrap();
+    assert_eq!(stat.entry_type, CotarEntryType::Symlink);
+    assert_eq!(stat.link_name.as_deref(), Some("tiles/1/0/0.pbf"));
+
+    assert!(cotar.stat("tiles/2/0/0.pbf").unwrap().is_none());
+}
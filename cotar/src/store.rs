@@ -0,0 +1,135 @@
+use std::io::Result as IoResult;
+use std::io::{Error, ErrorKind};
+use std::sync::Arc;
+
+use bytes::Bytes;
+use object_store::path::Path;
+use object_store::ObjectStore;
+
+use crate::async_cotar::{AsyncCotar, AsyncSource};
+use crate::cotar::DEFAULT_TAIL_SIZE;
+use crate::error::CotarResult;
+
+/// Read only view of an object using ranged GETs from an `object_store::ObjectStore`
+///
+/// Works with any store supported by `object_store` such as S3, GCS, Azure or the local file system
+#[derive(Debug, Clone)]
+pub struct ObjectStoreView {
+    store: Arc<dyn ObjectStore>,
+    path: Path,
+    pub size: u64,
+}
+
+impl ObjectStoreView {
+    /// Open an object, the size of the object is read with a HEAD request
+    pub async fn open(store: Arc<dyn ObjectStore>, path: Path) -> IoResult<Self> {
+        let meta = store.head(&path).await?;
+
+        Ok(ObjectStoreView {
+            store,
+            path,
+            size: meta.size,
+        })
+    }
+}
+
+impl AsyncSource for ObjectStoreView {
+    fn size(&self) -> u64 {
+        self.size
+    }
+
+    async fn read_exact(&self, offset: u64, len: u64) -> IoResult<Bytes> {
+        if len == 0 {
+            return Ok(Bytes::new());
+        }
+        let end = offset
+            .checked_add(len)
+            .filter(|end| *end <= self.size)
+            .ok_or_else(|| Error::new(ErrorKind::UnexpectedEof, "failed to fill whole buffer"))?;
+
+        let bytes = self.store.get_range(&self.path, offset..end).await?;
+        if bytes.len() as u64 != len {
+            return Err(Error::new(
+                ErrorKind::UnexpectedEof,
+                "failed to fill whole buffer",
+            ));
+        }
+        Ok(bytes)
+    }
+}
+
+impl AsyncCotar<ObjectStoreView> {
+    /// Load a cotar from a packed tar stored in an object store
+    ///
+    /// The index of the tar must be the final bytes of the tar file, the last `DEFAULT_TAIL_SIZE`
+    /// bytes are read with a single request see `Cotar::from_source_with_tail`
+    pub async fn from_object_store(store: Arc<dyn ObjectStore>, path: Path) -> CotarResult<Self> {
+        let view = ObjectStoreView::open(store, path).await?;
+        AsyncCotar::from_source_with_tail(view, DEFAULT_TAIL_SIZE).await
+    }
+
+    /// Load a cotar from a tar and index file stored in an object store
+    pub async fn from_object_store_index(
+        store: Arc<dyn ObjectStore>,
+        tar_path: Path,
+        index_path: Path,
+    ) -> CotarResult<Self> {
+        AsyncCotar::from_sources(
+            ObjectStoreView::open(store.clone(), tar_path).await?,
+            ObjectStoreView::open(store, index_path).await?,
+        )
+        .await
+    }
+}
+
+#[tokio::test]
+async fn test_object_store_memory() {
+    use crate::test_util::{create_archive, TEST_FILES};
+    use object_store::memory::InMemory;
+
+    let archive = create_archive("object_store_memory", &TEST_FILES);
+    let store: Arc<dyn ObjectStore> = Arc::new(InMemory::new());
+    for (name, file) in [
+        ("archive.tar.co", &archive.embedded),
+        ("archive.tar", &archive.tar),
+        ("archive.tar.index", &archive.index),
+    ] {
+        let data = std::fs::read(file).unwrap();
+        store.put(&Path::from(name), data.into()).await.unwrap();
+    }
+
+    let cotar = AsyncCotar::from_object_store(store.clone(), Path::from("archive.tar.co"));
+    crate::test_util::assert_test_files_async(&cotar.await.unwrap()).await;
+
+    let cotar = AsyncCotar::from_object_store_index(
+        store.clone(),
+        Path::from("archive.tar"),
+        Path::from("archive.tar.index"),
+    );
+    crate::test_util::assert_test_files_async(&cotar.await.unwrap()).await;
+
+    let err = ObjectStoreView::open(store, Path::from("missing.tar"))
+        .await
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+}
+
+#[tokio::test]
+async fn test_object_store_local() {
+    use crate::test_util::{create_archive, TEST_FILES};
+    use object_store::local::LocalFileSystem;
+
+    let archive = create_archive("object_store_local", &TEST_FILES);
+    let dir = archive.tar.parent().unwrap();
+    let store: Arc<dyn ObjectStore> = Arc::new(LocalFileSystem::new_with_prefix(dir).unwrap());
+
+    let cotar = AsyncCotar::from_object_store(store.clone(), Path::from("archive.tar.co"));
+    crate::test_util::assert_test_files_async(&cotar.await.unwrap()).await;
+
+    let cotar = AsyncCotar::from_object_store_index(
+        store,
+        Path::from("archive.tar"),
+        Path::from("archive.tar.index"),
+    );
+    crate::test_util::assert_test_files_async(&cotar.await.unwrap()).await;
+}
@@ -0,0 +1,239 @@
+use crate::error::{CotarError, CotarResult};
+use std::borrow::Cow;
+
+use tar::{EntryType, Header, PaxExtensions};
+
+/// Size of the tar header in front of every file
+pub const TAR_HEADER_SIZE: u64 = 512;
+
+/// Bytes in front of a tar header that are searched for GNU long name and PAX extended headers
+pub const TAR_EXTENSION_LOOKBACK: u64 = 16 * TAR_HEADER_SIZE;
+
+/// Largest lookback searched for extension headers when verifying a path that does not fit in
+/// the tar header
+pub(crate) const MAX_EXTENSION_LOOKBACK: u64 = 1024 * TAR_HEADER_SIZE;
+
+/// Maximum number of extension headers that can be chained in front of a file header
+const MAX_EXTENSIONS: usize = 4;
+
+/// Tar header in front of a file with any GNU long name or PAX extended headers that precede it
+pub(crate) struct TarHeader {
+    pub header: Header,
+    /// GNU long name `L` extension
+    long_name: Option<Vec<u8>>,
+    /// GNU long link name `K` extension
+    long_link_name: Option<Vec<u8>>,
+    /// PAX `x` extended header records
+    pax: Option<Vec<u8>>,
+}
+
+/// Byte range to read to parse the tar header of the file at `file_offset`
+///
+/// Includes up to `lookback` bytes before the header to search for extension headers
+pub(crate) fn header_range(file_offset: u64, lookback: u64) -> CotarResult<(u64, u64)> {
+    let header_offset = file_offset
+        .checked_sub(TAR_HEADER_SIZE)
+        .ok_or(CotarError::InvalidTarHeader { file_offset })?;
+    let start = header_offset.saturating_sub(lookback);
+    Ok((start, file_offset - start))
+}
+
+/// Tar header checksum, the sum of all bytes with the checksum field as spaces
+fn checksum(block: &[u8]) -> u32 {
+    block[..148]
+        .iter()
+        .chain(&block[156..])
+        .fold(8 * 32, |sum, byte| sum + *byte as u32)
+}
+
+/// Parse a block as a header if it looks like a valid tar header
+fn parse_block(block: &[u8]) -> Option<&Header> {
+    let header = Header::from_byte_slice(block);
+    match header.cksum() {
+        Ok(cksum) if cksum == checksum(block) => Some(header),
+        _ => None,
+    }
+}
+
+impl TarHeader {
+    /// Parse a tar header from `bytes` where the last 512 bytes are the header of the file
+    ///
+    /// Any bytes before the header are searched backwards for extension headers.
+    ///
+    /// Returns None if the bytes do not end with a valid tar header
+    pub fn parse(bytes: &[u8]) -> Option<TarHeader> {
+        if (bytes.len() as u64) < TAR_HEADER_SIZE {
+            return None;
+        }
+        let block_size = TAR_HEADER_SIZE as usize;
+        let header_start = bytes.len() - block_size;
+        let header = parse_block(&bytes[header_start..])?;
+
+        let mut tar_header = TarHeader {
+            header: header.clone(),
+            long_name: None,
+            long_link_name: None,
+            pax: None,
+        };
+
+        // Extension headers are directly followed by their data then the next header,
+        // walk backwards one block at a time looking for a header whose data ends at `end`
+        let mut end = header_start;
+        for _ in 0..MAX_EXTENSIONS {
+            let mut found = false;
+            let mut data_blocks = 0;
+            while end >= (data_blocks + 1) * block_size {
+                let start = end - (data_blocks + 1) * block_size;
+                if let Some(ext) = parse_block(&bytes[start..start + block_size]) {
+                    let size = ext.size().unwrap_or(u64::MAX) as usize;
+                    let blocks = size.div_ceil(block_size);
+                    // GNU long names written by tar-rs have a trailing null byte not included in the size
+                    let gnu_blocks = size.saturating_add(1).div_ceil(block_size);
+                    let data = &bytes[start + block_size..end];
+                    let data = &data[..size.min(data.len())];
+                    let matched = match ext.entry_type() {
+                        EntryType::GNULongName
+                            if data_blocks == blocks || data_blocks == gnu_blocks =>
+                        {
+                            tar_header
+                                .long_name
+                                .get_or_insert_with(|| trim_null(data).to_vec());
+                            true
+                        }
+                        EntryType::GNULongLink
+                            if data_blocks == blocks || data_blocks == gnu_blocks =>
+                        {
+                            tar_header
+                                .long_link_name
+                                .get_or_insert_with(|| trim_null(data).to_vec());
+                            true
+                        }
+                        EntryType::XHeader if data_blocks == blocks => {
+                            tar_header.pax.get_or_insert_with(|| data.to_vec());
+                            true
+                        }
+                        _ => false,
+                    };
+                    if matched {
+                        end = start;
+                        found = true;
+                        break;
+                    }
+                }
+                data_blocks += 1;
+            }
+            if !found {
+                break;
+            }
+        }
+
+        Some(tar_header)
+    }
+
+    /// Were any extension headers found in front of the header
+    pub fn has_extensions(&self) -> bool {
+        self.long_name.is_some() || self.long_link_name.is_some() || self.pax.is_some()
+    }
+
+    /// PAX extended header value for `key`
+    pub fn pax_value(&self, key: &str) -> Option<&[u8]> {
+        let pax = self.pax.as_ref()?;
+        PaxExtensions::new(pax)
+            .filter_map(|ext| ext.ok())
+            .find(|ext| ext.key_bytes() == key.as_bytes())
+            .map(|ext| ext.value_bytes())
+    }
+
+    /// Full path of the file, preferring PAX then GNU long names over the header name
+    pub fn path_bytes(&self) -> Cow<'_, [u8]> {
+        if let Some(path) = self.pax_value("path") {
+            return Cow::Borrowed(path);
+        }
+        match &self.long_name {
+            Some(name) => Cow::Borrowed(name),
+            None => self.header.path_bytes(),
+        }
+    }
+
+    /// Target of a link, preferring PAX then GNU long link names over the header link name
+    pub fn link_name_bytes(&self) -> Option<Cow<'_, [u8]>> {
+        if let Some(link_name) = self.pax_value("linkpath") {
+            return Some(Cow::Borrowed(link_name));
+        }
+        match &self.long_link_name {
+            Some(link_name) => Some(Cow::Borrowed(link_name)),
+            None => self.header.link_name_bytes(),
+        }
+    }
+}
+
+fn trim_null(data: &[u8]) -> &[u8] {
+    match data.iter().position(|byte| *byte == 0) {
+        Some(end) => &data[..end],
+        None => data,
+    }
+}
+
+#[cfg(test)]
+fn read_header(tar: &[u8], file_offset: u64) -> TarHeader {
+    let (offset, len) = header_range(file_offset, TAR_EXTENSION_LOOKBACK).unwrap();
+    TarHeader::parse(&tar[offset as usize..(offset + len) as usize]).unwrap()
+}
+
+#[test]
+fn test_parse_headers() {
+    use tar::{Archive, Builder};
+
+    let long_path = format!("tiles/{}/0/0.pbf", "a".repeat(200));
+    let long_link = format!("links/{}/0/0.pbf", "b".repeat(600));
+    let pax_path = format!("tiles/{}/1/1.pbf", "c".repeat(150));
+
+    let mut builder = Builder::new(Vec::new());
+    for path in ["tiles/0/0/0.pbf", long_path.as_str()] {
+        let mut header = Header::new_gnu();
+        header.set_size(4);
+        builder
+            .append_data(&mut header, path, &b"data"[..])
+            .unwrap();
+    }
+    let mut header = Header::new_gnu();
+    header.set_size(0);
+    header.set_entry_type(EntryType::Link);
+    builder
+        .append_link(&mut header, &long_link, &long_path)
+        .unwrap();
+
+    builder
+        .append_pax_extensions([("path", pax_path.as_bytes()), ("mtime", b"1.5".as_slice())])
+        .unwrap();
+    let mut header = Header::new_ustar();
+    header.set_size(4);
+    header.set_path("tiles/truncated.pbf").unwrap();
+    header.set_cksum();
+    builder.append(&header, &b"data"[..]).unwrap();
+    let tar = builder.into_inner().unwrap();
+
+    let mut expected = vec![
+        ("tiles/0/0/0.pbf".to_string(), None),
+        (long_path.clone(), None),
+        (long_link, Some(long_path)),
+        (pax_path, None),
+    ]
+    .into_iter();
+    let mut archive = Archive::new(tar.as_slice());
+    for entry in archive.entries().unwrap() {
+        let entry = entry.unwrap();
+        let header = read_header(&tar, entry.raw_file_position());
+        let (path, link) = expected.next().unwrap();
+        assert_eq!(header.path_bytes().as_ref(), path.as_bytes());
+        assert_eq!(
+            header.link_name_bytes().map(|link| link.into_owned()),
+            link.map(|link| link.into_bytes())
+        );
+    }
+    assert!(expected.next().is_none());
+
+    // Not a tar header
+    assert!(TarHeader::parse(&tar[1..513]).is_none());
+    assert!(header_range(100, TAR_EXTENSION_LOOKBACK).is_err());
+}
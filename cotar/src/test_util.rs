@@ -0,0 +1,253 @@
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use bytes::Bytes;
+use tar::{Builder, EntryType, Header};
+use tempfile::TempDir;
+
+use crate::{Cotar, CotarIndex, Source};
+
+/// Files packed into every test archive
+pub const TEST_FILES: [(&str, &[u8]); 4] = [
+    ("tiles/0/0/0.pbf", b"tile 0/0/0"),
+    ("tiles/1/0/0.pbf", b"tile 1/0/0 with a little more data"),
+    ("tiles/1/1/0.pbf", b"tile 1/1/0"),
+    ("README.md", b"# Test archive"),
+];
+
+/// Paths to a tar with a separate index and the same tar with the index appended
+///
+/// The files are removed when the archive is dropped
+pub struct TestArchive {
+    pub tar: PathBuf,
+    pub index: PathBuf,
+    pub embedded: PathBuf,
+    _dir: TempDir,
+}
+
+/// Create a tar from `files` in a temporary folder prefixed with `name` and index it
+pub fn create_archive(name: &str, files: &[(&str, &[u8])]) -> TestArchive {
+    create_archive_with_links(name, files, &[])
+}
+
+/// Create a tar from `files` followed by hard `links` from a path to a file and index it
+pub fn create_archive_with_links(
+    name: &str,
+    files: &[(&str, &[u8])],
+    links: &[(&str, &str)],
+) -> TestArchive {
+    let temp_dir = tempfile::Builder::new()
+        .prefix(&format!("cotar-test-{}-", name))
+        .tempdir()
+        .unwrap();
+    let dir = temp_dir.path();
+
+    let tar = dir.join("archive.tar");
+    let mut builder = Builder::new(std::fs::File::create(&tar).unwrap());
+    for (path, data) in files {
+        let mut header = Header::new_gnu();
+        header.set_size(data.len() as u64);
+        builder.append_data(&mut header, path, *data).unwrap();
+    }
+    for (path, target) in links {
+        let mut header = Header::new_gnu();
+        header.set_entry_type(EntryType::Link);
+        header.set_size(0);
+        builder.append_link(&mut header, path, target).unwrap();
+    }
+    builder.into_inner().unwrap();
+
+    let tar_str = tar.to_str().unwrap();
+    let mut cotar_index = CotarIndex::from_tar(tar_str, 0).unwrap();
+    let packed = cotar_index.pack(1.25).unwrap();
+
+    let index = dir.join("archive.tar.index");
+    std::fs::write(&index, &packed.vec).unwrap();
+
+    let embedded = dir.join("archive.tar.co");
+    let mut embedded_bytes = std::fs::read(&tar).unwrap();
+    embedded_bytes.extend_from_slice(&packed.vec);
+    std::fs::write(&embedded, embedded_bytes).unwrap();
+
+    TestArchive {
+        tar,
+        index,
+        embedded,
+        _dir: temp_dir,
+    }
+}
+
+impl TestArchive {
+    pub fn tar(&self) -> &str {
+        self.tar.to_str().unwrap()
+    }
+
+    pub fn index(&self) -> &str {
+        self.index.to_str().unwrap()
+    }
+
+    pub fn embedded(&self) -> &str {
+        self.embedded.to_str().unwrap()
+    }
+}
+
+/// Assert every file in [TEST_FILES] can be read from `cotar` and a missing path is not found
+pub fn assert_test_files<S: Source>(cotar: &Cotar<S>) {
+    for (path, data) in TEST_FILES {
+        assert_eq!(cotar.get(path).unwrap().unwrap().as_ref(), data);
+    }
+    assert!(cotar.get("tiles/2/0/0.pbf").unwrap().is_none());
+}
+
+/// Assert every file in [TEST_FILES] can be read from `cotar` and a missing path is not found
+#[cfg(feature = "tokio")]
+pub async fn assert_test_files_async<S: crate::AsyncSource>(cotar: &crate::AsyncCotar<S>) {
+    for (path, data) in TEST_FILES {
+        assert_eq!(cotar.get(path).await.unwrap().unwrap().as_ref(), data);
+    }
+    assert!(cotar.get("tiles/2/0/0.pbf").await.unwrap().is_none());
+}
+
+/// Source wrapper that counts the number of reads made
+pub struct CountingSource<S> {
+    source: S,
+    reads: AtomicUsize,
+}
+
+impl<S> CountingSource<S> {
+    pub fn new(source: S) -> Self {
+        CountingSource {
+            source,
+            reads: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn reads(&self) -> usize {
+        self.reads.load(Ordering::Relaxed)
+    }
+}
+
+impl<S: Source> Source for CountingSource<S> {
+    fn size(&self) -> u64 {
+        self.source.size()
+    }
+
+    fn read_exact(&self, offset: u64, len: u64) -> std::io::Result<Bytes> {
+        self.reads.fetch_add(1, Ordering::Relaxed);
+        self.source.read_exact(offset, len)
+    }
+}
+
+/// Minimal HTTP/1.1 server that supports HEAD and GET requests with a single `Range`
+#[cfg(any(feature = "http", feature = "http-async"))]
+pub struct TestServer {
+    address: std::net::SocketAddr,
+    requests: std::sync::Arc<AtomicUsize>,
+}
+
+#[cfg(any(feature = "http", feature = "http-async"))]
+impl TestServer {
+    pub fn url(&self, path: &str) -> String {
+        format!("http://{}{}", self.address, path)
+    }
+
+    /// Number of GET requests served
+    pub fn requests(&self) -> usize {
+        self.requests.load(Ordering::Relaxed)
+    }
+}
+
+/// Serve `files` from a local server until the test process exits
+#[cfg(any(feature = "http", feature = "http-async"))]
+pub fn serve_files(files: &[(&str, Vec<u8>)]) -> TestServer {
+    use std::collections::HashMap;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::sync::Arc;
+
+    let files: Arc<HashMap<String, Vec<u8>>> = Arc::new(
+        files
+            .iter()
+            .map(|(path, data)| (path.to_string(), data.clone()))
+            .collect(),
+    );
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    let requests = Arc::new(AtomicUsize::new(0));
+
+    let counter = requests.clone();
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(mut stream) = stream else { continue };
+            let files = files.clone();
+            let counter = counter.clone();
+            std::thread::spawn(move || {
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                loop {
+                    let mut request_line = String::new();
+                    if reader.read_line(&mut request_line).unwrap_or(0) == 0 {
+                        return;
+                    }
+                    let mut parts = request_line.split_whitespace();
+                    let method = parts.next().unwrap_or_default().to_string();
+                    let path = parts.next().unwrap_or_default().to_string();
+                    if method == "GET" {
+                        counter.fetch_add(1, Ordering::Relaxed);
+                    }
+
+                    let mut range: Option<(u64, u64)> = None;
+                    loop {
+                        let mut line = String::new();
+                        reader.read_line(&mut line).unwrap();
+                        let line = line.trim_end();
+                        if line.is_empty() {
+                            break;
+                        }
+                        let (name, value) = line.split_once(':').unwrap();
+                        if name.eq_ignore_ascii_case("range") {
+                            let (start, end) = value
+                                .trim()
+                                .trim_start_matches("bytes=")
+                                .split_once('-')
+                                .unwrap();
+                            range = Some((start.parse().unwrap(), end.parse().unwrap()));
+                        }
+                    }
+
+                    let response = match files.get(&path) {
+                        None => b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n".to_vec(),
+                        Some(data) => {
+                            let (status, body) = match range {
+                                Some((start, end)) if method == "GET" => (
+                                    format!(
+                                        "206 Partial Content\r\nContent-Range: bytes {}-{}/{}",
+                                        start,
+                                        end,
+                                        data.len()
+                                    ),
+                                    &data[start as usize..=end as usize],
+                                ),
+                                _ => (String::from("200 OK"), &data[..]),
+                            };
+                            let mut response = format!(
+                                "HTTP/1.1 {}\r\nContent-Length: {}\r\n\r\n",
+                                status,
+                                body.len()
+                            )
+                            .into_bytes();
+                            if method == "GET" {
+                                response.extend_from_slice(body);
+                            }
+                            response
+                        }
+                    };
+                    if stream.write_all(&response).is_err() {
+                        return;
+                    }
+                }
+            });
+        }
+    });
+
+    TestServer { address, requests }
+}